@@ -5,6 +5,7 @@ use regex::Regex;
 
 pub mod assign;
 pub mod close;
+pub mod concern;
 pub mod glacier;
 pub mod nominate;
 pub mod note;
@@ -26,6 +27,7 @@ pub enum Command<'a> {
     Shortcut(Result<shortcut::ShortcutCommand, Error<'a>>),
     Close(Result<close::CloseCommand, Error<'a>>),
     Note(Result<note::NoteCommand, Error<'a>>),
+    Concern(Result<concern::ConcernCommand, Error<'a>>),
 }
 
 #[derive(Debug)]
@@ -36,6 +38,8 @@ pub struct Input<'a> {
     /// A pattern for finding the start of a command based on the name of the
     /// configured bots.
     bot_re: Regex,
+    /// The names of the shortcut commands, see [`Input::with_shortcuts`].
+    shortcuts: Vec<String>,
 }
 
 fn parse_single_command<'a, T, F, M>(
@@ -71,9 +75,19 @@ impl<'a> Input<'a> {
             parsed: 0,
             ignore: IgnoreBlocks::new(input),
             bot_re,
+            shortcuts: Vec::new(),
         }
     }
 
+    /// Sets the names of the shortcut commands to recognize.
+    ///
+    /// Shortcuts are only parsed if no other command matches, so they can't
+    /// shadow the built-in commands.
+    pub fn with_shortcuts(mut self, shortcuts: Vec<String>) -> Input<'a> {
+        self.shortcuts = shortcuts;
+        self
+    }
+
     fn parse_command(&mut self) -> Option<Command<'a>> {
         let tok = Tokenizer::new(&self.all[self.parsed..]);
         log::info!("identified potential command");
@@ -123,16 +137,24 @@ impl<'a> Input<'a> {
             &original_tokenizer,
         ));
         success.extend(parse_single_command(
-            shortcut::ShortcutCommand::parse,
-            Command::Shortcut,
+            close::CloseCommand::parse,
+            Command::Close,
             &original_tokenizer,
         ));
         success.extend(parse_single_command(
-            close::CloseCommand::parse,
-            Command::Close,
+            concern::ConcernCommand::parse,
+            Command::Concern,
             &original_tokenizer,
         ));
 
+        if success.is_empty() {
+            success.extend(parse_single_command(
+                |tok| shortcut::ShortcutCommand::parse(tok, &self.shortcuts),
+                Command::Shortcut,
+                &original_tokenizer,
+            ));
+        }
+
         if success.len() > 1 {
             panic!(
                 "succeeded parsing {:?} to multiple commands: {:?}",
@@ -207,6 +229,7 @@ impl<'a> Command<'a> {
             Command::Shortcut(r) => r.is_ok(),
             Command::Close(r) => r.is_ok(),
             Command::Note(r) => r.is_ok(),
+            Command::Concern(r) => r.is_ok(),
         }
     }
 
@@ -315,7 +338,7 @@ fn review_commands() {
         assert_eq!(
             input.next(),
             Some(Command::Assign(Ok(assign::AssignCommand::ReviewName {
-                name: name.to_string()
+                names: vec![name.to_string()]
             })))
         );
         assert_eq!(input.next(), None);
@@ -347,3 +370,19 @@ fn review_ignored() {
         assert_eq!(input.next(), None);
     }
 }
+
+#[test]
+fn shortcuts() {
+    let input = "@bot ready @bot close @bot author";
+    let shortcuts = vec!["ready".to_string(), "close".to_string()];
+    let mut input = Input::new(input, vec!["bot"]).with_shortcuts(shortcuts);
+    assert_eq!(
+        input.next(),
+        Some(Command::Shortcut(Ok(shortcut::ShortcutCommand {
+            name: "ready".to_string()
+        })))
+    );
+    // Built-in commands take precedence over shortcuts.
+    assert!(matches!(input.next(), Some(Command::Close(Ok(_)))));
+    assert_eq!(input.next(), None);
+}
@@ -6,6 +6,8 @@
 //!
 //! ```text
 //! Command: `@bot claim`, `@bot release-assignment`, or `@bot assign @user`.
+//! Review: `r? name` or `r? @user @user2 ...`, where each name is a user, team or group.
+//! Explain: `r? explain`, to show how a reviewer would be chosen.
 //! ```
 
 use crate::error::Error;
@@ -17,7 +19,8 @@ pub enum AssignCommand {
     Own,
     Release,
     User { username: String },
-    ReviewName { name: String },
+    ReviewName { names: Vec<String> },
+    ReviewExplain,
 }
 
 #[derive(PartialEq, Eq, Debug)]
@@ -79,17 +82,39 @@ impl AssignCommand {
     }
 
     /// Parses the input for `r?` command.
+    ///
+    /// Names after the first one must start with `@`, so that the rest of a
+    /// sentence like `r? @octocat can you review?` isn't taken as names.
     pub fn parse_review<'a>(input: &mut Tokenizer<'a>) -> Result<Option<Self>, Error<'a>> {
-        match input.next_token() {
+        let mut names = match input.next_token() {
+            // `r? @explain` still requests a review from the user of that name.
+            Ok(Some(Token::Word("explain"))) => return Ok(Some(AssignCommand::ReviewExplain)),
             Ok(Some(Token::Word(name))) => {
                 let name = name.strip_prefix('@').unwrap_or(name).to_string();
                 if name.is_empty() {
                     return Err(input.error(ParseError::NoUser));
                 }
-                Ok(Some(AssignCommand::ReviewName { name }))
+                vec![name]
+            }
+            _ => return Err(input.error(ParseError::NoUser)),
+        };
+        loop {
+            let mut toks = input.clone();
+            if let Ok(Some(Token::Comma)) = toks.peek_token() {
+                toks.next_token()?;
+            }
+            match toks.next_token() {
+                Ok(Some(Token::Word(name))) if name.starts_with('@') && name.len() > 1 => {
+                    let name = name[1..].to_string();
+                    if !names.contains(&name) {
+                        names.push(name);
+                    }
+                    *input = toks;
+                }
+                _ => break,
             }
-            _ => Err(input.error(ParseError::NoUser)),
         }
+        Ok(Some(AssignCommand::ReviewName { names }))
     }
 }
 
@@ -155,7 +180,44 @@ mod tests {
             assert_eq!(
                 parse_review(input),
                 Ok(Some(AssignCommand::ReviewName {
-                    name: name.to_string()
+                    names: vec![name.to_string()]
+                })),
+                "failed on {input}"
+            );
+        }
+    }
+
+    #[test]
+    fn review_explain() {
+        assert_eq!(
+            parse_review("explain"),
+            Ok(Some(AssignCommand::ReviewExplain))
+        );
+        assert_eq!(
+            parse_review("@explain"),
+            Ok(Some(AssignCommand::ReviewName {
+                names: vec!["explain".to_string()]
+            }))
+        );
+    }
+
+    #[test]
+    fn review_multiple_names() {
+        for (input, names) in [
+            ("@a @b", &["a", "b"][..]),
+            ("@a, @b.", &["a", "b"]),
+            (
+                "compiler @a @rust-lang/libs",
+                &["compiler", "a", "rust-lang/libs"],
+            ),
+            ("@a b @c", &["a"]),
+            ("@a @a", &["a"]),
+            ("@a @", &["a"]),
+        ] {
+            assert_eq!(
+                parse_review(input),
+                Ok(Some(AssignCommand::ReviewName {
+                    names: names.iter().map(|n| n.to_string()).collect()
                 })),
                 "failed on {input}"
             );
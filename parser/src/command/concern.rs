@@ -0,0 +1,154 @@
+//! The concern command parser.
+//!
+//! This allows raising and resolving named concerns on major change
+//! proposals. A proposal isn't accepted while it has unresolved concerns.
+//!
+//! The grammar is as follows:
+//!
+//! ```text
+//! Command: `@bot concern <name>` or `@bot resolve <name>`
+//!
+//! <name>: a word, or a quoted string
+//! ```
+
+use crate::error::Error;
+use crate::token::{Token, Tokenizer};
+use std::fmt;
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum ConcernCommand {
+    Concern { name: String },
+    Resolve { name: String },
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum ParseError {
+    MissingName,
+    ExpectedEnd,
+}
+
+impl std::error::Error for ParseError {}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::MissingName => write!(f, "missing the name of the concern"),
+            ParseError::ExpectedEnd => write!(
+                f,
+                "expected end of command, names of several words must be quoted"
+            ),
+        }
+    }
+}
+
+impl ConcernCommand {
+    pub fn parse<'a>(input: &mut Tokenizer<'a>) -> Result<Option<Self>, Error<'a>> {
+        let mut toks = input.clone();
+        let resolve = match toks.peek_token()? {
+            Some(Token::Word("concern")) => false,
+            Some(Token::Word("resolve")) => true,
+            _ => return Ok(None),
+        };
+        toks.next_token()?;
+        let name = match toks.next_token()? {
+            Some(Token::Word(name)) | Some(Token::Quote(name)) if !name.is_empty() => {
+                name.to_string()
+            }
+            _ => return Err(toks.error(ParseError::MissingName)),
+        };
+        match toks.peek_token()? {
+            None => {}
+            Some(Token::Dot) | Some(Token::EndOfLine) => {
+                toks.next_token()?;
+            }
+            Some(_) => return Err(toks.error(ParseError::ExpectedEnd)),
+        }
+        *input = toks;
+        Ok(Some(if resolve {
+            ConcernCommand::Resolve { name }
+        } else {
+            ConcernCommand::Concern { name }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse<'a>(input: &'a str) -> Result<Option<ConcernCommand>, Error<'a>> {
+        let mut toks = Tokenizer::new(input);
+        Ok(ConcernCommand::parse(&mut toks)?)
+    }
+
+    #[test]
+    fn concern() {
+        assert_eq!(
+            parse("concern stability"),
+            Ok(Some(ConcernCommand::Concern {
+                name: "stability".to_string()
+            }))
+        );
+        assert_eq!(
+            parse(r#"concern "needs a design meeting""#),
+            Ok(Some(ConcernCommand::Concern {
+                name: "needs a design meeting".to_string()
+            }))
+        );
+    }
+
+    #[test]
+    fn resolve() {
+        assert_eq!(
+            parse("resolve stability."),
+            Ok(Some(ConcernCommand::Resolve {
+                name: "stability".to_string()
+            }))
+        );
+    }
+
+    #[test]
+    fn missing_name() {
+        use std::error::Error;
+        assert_eq!(
+            parse("concern")
+                .unwrap_err()
+                .source()
+                .unwrap()
+                .downcast_ref(),
+            Some(&ParseError::MissingName),
+        );
+        assert_eq!(
+            parse("resolve.")
+                .unwrap_err()
+                .source()
+                .unwrap()
+                .downcast_ref(),
+            Some(&ParseError::MissingName),
+        );
+    }
+
+    #[test]
+    fn unquoted_words() {
+        use std::error::Error;
+        assert_eq!(
+            parse("concern needs docs")
+                .unwrap_err()
+                .source()
+                .unwrap()
+                .downcast_ref(),
+            Some(&ParseError::ExpectedEnd),
+        );
+        assert_eq!(
+            parse("concern stability\nmore text"),
+            Ok(Some(ConcernCommand::Concern {
+                name: "stability".to_string()
+            }))
+        );
+    }
+
+    #[test]
+    fn other() {
+        assert_eq!(parse("concerned"), Ok(None));
+    }
+}
@@ -1,23 +1,22 @@
 //! The shortcut command parser.
 //!
-//! This can parse predefined shortcut input, single word commands.
+//! This can parse predefined shortcut input, single word commands. The
+//! available shortcuts are configured per repository.
 //!
 //! The grammar is as follows:
 //!
 //! ```text
-//! Command: `@bot ready`/`@bot review`, or `@bot author`.
+//! Command: `@bot <shortcut>`, for example `@bot ready` or `@bot author`.
 //! ```
 
 use crate::error::Error;
 use crate::token::{Token, Tokenizer};
-use std::collections::HashMap;
 use std::fmt;
 
-#[derive(PartialEq, Eq, Debug, Copy, Clone)]
-pub enum ShortcutCommand {
-    Ready,
-    Author,
-    Blocked,
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ShortcutCommand {
+    /// The shortcut, as it was written in the comment.
+    pub name: String,
 }
 
 #[derive(PartialEq, Eq, Debug)]
@@ -32,23 +31,21 @@ impl fmt::Display for ParseError {
 }
 
 impl ShortcutCommand {
-    pub fn parse<'a>(input: &mut Tokenizer<'a>) -> Result<Option<Self>, Error<'a>> {
-        let mut shortcuts = HashMap::new();
-        shortcuts.insert("ready", ShortcutCommand::Ready);
-        shortcuts.insert("review", ShortcutCommand::Ready);
-        shortcuts.insert("reviewer", ShortcutCommand::Ready);
-        shortcuts.insert("author", ShortcutCommand::Author);
-        shortcuts.insert("blocked", ShortcutCommand::Blocked);
-
+    /// Parses one of the given shortcut names.
+    pub fn parse<'a>(
+        input: &mut Tokenizer<'a>,
+        shortcuts: &[String],
+    ) -> Result<Option<Self>, Error<'a>> {
         let mut toks = input.clone();
         if let Some(Token::Word(word)) = toks.peek_token()? {
-            if !shortcuts.contains_key(word) {
+            if !shortcuts.iter().any(|name| name == word) {
                 return Ok(None);
             }
             toks.next_token()?;
             *input = toks;
-            let command = shortcuts.get(word).unwrap();
-            return Ok(Some(*command));
+            return Ok(Some(ShortcutCommand {
+                name: word.to_string(),
+            }));
         }
         Ok(None)
     }
@@ -56,31 +53,44 @@ impl ShortcutCommand {
 
 #[cfg(test)]
 fn parse(input: &str) -> Result<Option<ShortcutCommand>, Error<'_>> {
+    let shortcuts = ["ready", "author", "blocked"].map(String::from);
     let mut toks = Tokenizer::new(input);
-    Ok(ShortcutCommand::parse(&mut toks)?)
+    Ok(ShortcutCommand::parse(&mut toks, &shortcuts)?)
+}
+
+#[cfg(test)]
+fn shortcut(name: &str) -> Option<ShortcutCommand> {
+    Some(ShortcutCommand {
+        name: name.to_string(),
+    })
 }
 
 #[test]
 fn test_1() {
-    assert_eq!(parse("ready."), Ok(Some(ShortcutCommand::Ready)));
+    assert_eq!(parse("ready."), Ok(shortcut("ready")));
 }
 
 #[test]
 fn test_2() {
-    assert_eq!(parse("ready"), Ok(Some(ShortcutCommand::Ready)));
+    assert_eq!(parse("ready"), Ok(shortcut("ready")));
 }
 
 #[test]
 fn test_3() {
-    assert_eq!(parse("author"), Ok(Some(ShortcutCommand::Author)),);
+    assert_eq!(parse("author"), Ok(shortcut("author")),);
 }
 
 #[test]
 fn test_4() {
-    assert_eq!(parse("ready word"), Ok(Some(ShortcutCommand::Ready)));
+    assert_eq!(parse("ready word"), Ok(shortcut("ready")));
 }
 
 #[test]
 fn test_5() {
-    assert_eq!(parse("blocked"), Ok(Some(ShortcutCommand::Blocked)));
+    assert_eq!(parse("blocked"), Ok(shortcut("blocked")));
+}
+
+#[test]
+fn unknown_shortcut() {
+    assert_eq!(parse("review"), Ok(None));
 }
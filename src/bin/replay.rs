@@ -0,0 +1,36 @@
+//! Replays a webhook delivery stored in the `github_events` table through the
+//! handlers, using the local configuration (`DATABASE_URL`,
+//! `GITHUB_API_TOKEN`, etc.).
+
+use reqwest::Client;
+use triagebot::{db, github, handlers::Context};
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> anyhow::Result<()> {
+    dotenv::dotenv().ok();
+    tracing_subscriber::fmt::init();
+
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() != 2 {
+        eprintln!("Usage: replay <delivery id>");
+        std::process::exit(1);
+    }
+
+    let ctx = Context {
+        username: String::from("rustbot"),
+        db: db::ClientPool::new(),
+        github: github::GithubClient::new_with_default_token(Client::new()),
+        octocrab: octocrab::OctocrabBuilder::new()
+            .personal_token(github::default_token_from_env())
+            .build()
+            .expect("Failed to build octograb."),
+    };
+
+    if triagebot::replay_webhook(&args[1], &ctx).await? {
+        println!("processed {}", args[1]);
+    } else {
+        println!("ignored {}", args[1]);
+    }
+
+    Ok(())
+}
@@ -1,12 +1,14 @@
 use crate::changelogs::ChangelogFormat;
-use crate::github::{GithubClient, Repository};
+use crate::github::{GithubClient, PushEvent, Repository};
 use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::sync::{Arc, RwLock};
 use std::time::{Duration, Instant};
 use tracing as log;
 
-static CONFIG_FILE_NAME: &str = "triagebot.toml";
+pub(crate) static CONFIG_FILE_NAME: &str = "triagebot.toml";
+/// The repository of an organization holding its default configuration.
+static ORG_CONFIG_REPO: &str = ".github";
 const REFRESH_EVERY: Duration = Duration::from_secs(2 * 60); // Every two minutes
 
 lazy_static::lazy_static! {
@@ -16,7 +18,7 @@ lazy_static::lazy_static! {
 }
 
 #[derive(PartialEq, Eq, Debug, serde::Deserialize)]
-#[serde(rename_all = "kebab-case")]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub(crate) struct Config {
     pub(crate) relabel: Option<RelabelConfig>,
     pub(crate) assign: Option<AssignConfig>,
@@ -34,9 +36,13 @@ pub(crate) struct Config {
     pub(crate) note: Option<NoteConfig>,
     pub(crate) mentions: Option<MentionsConfig>,
     pub(crate) no_merges: Option<NoMergesConfig>,
+    pub(crate) dry_run: Option<DryRunConfig>,
+    pub(crate) notification: Option<NotificationConfig>,
+    pub(crate) required_reviews: Option<RequiredReviewsConfig>,
 }
 
 #[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
 pub(crate) struct NominateConfig {
     // team name -> label
     pub(crate) teams: HashMap<String, String>,
@@ -47,7 +53,7 @@ pub(crate) struct PingConfig {
     // team name -> message
     // message will have the cc string appended
     #[serde(flatten)]
-    teams: HashMap<String, PingTeamConfig>,
+    pub(crate) teams: HashMap<String, PingTeamConfig>,
 }
 
 impl PingConfig {
@@ -67,6 +73,7 @@ impl PingConfig {
 }
 
 #[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
 pub(crate) struct PingTeamConfig {
     pub(crate) message: String,
     #[serde(default)]
@@ -75,6 +82,7 @@ pub(crate) struct PingTeamConfig {
 }
 
 #[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
 pub(crate) struct AssignConfig {
     /// If `true`, then posts a warning comment if the PR is opened against a
     /// different branch than the default (usually master or main).
@@ -87,11 +95,158 @@ pub(crate) struct AssignConfig {
     pub(crate) adhoc_groups: HashMap<String, Vec<String>>,
     /// Users to assign when a new PR is opened.
     /// The key is a gitignore-style path, and the value is a list of
-    /// usernames, team names, or ad-hoc groups.
+    /// usernames, team names, or ad-hoc groups, optionally with the number of
+    /// reviewers to assign.
+    #[serde(default)]
+    pub(crate) owners: HashMap<String, Owners>,
+    /// If `true`, files that don't match any `owners` path are assigned
+    /// based on the repository's CODEOWNERS file on the default branch.
     #[serde(default)]
-    pub(crate) owners: HashMap<String, Vec<String>>,
+    pub(crate) codeowners: bool,
     #[serde(default)]
     pub(crate) users_on_vacation: HashSet<String>,
+    /// How to choose a reviewer among the candidates.
+    #[serde(default)]
+    pub(crate) selection: ReviewerSelection,
+    /// Pings or reassigns reviewers who haven't looked at a PR in a while.
+    pub(crate) stale_review: Option<StaleReviewConfig>,
+    /// How the reviewer of a PR is set.
+    #[serde(default)]
+    pub(crate) review_mode: ReviewMode,
+}
+
+/// The owners of a path in `assign.owners`, either as a plain list or as a
+/// table such as `{ reviewers = ["compiler"], min_reviewers = 2 }`.
+#[derive(PartialEq, Eq, Debug)]
+pub(crate) enum Owners {
+    Reviewers(Vec<String>),
+    Table {
+        reviewers: Vec<String>,
+        /// How many distinct reviewers to assign from `reviewers`.
+        min_reviewers: usize,
+    },
+}
+
+fn default_min_reviewers() -> usize {
+    1
+}
+
+/// Deserialized by hand rather than as an untagged enum, so that a misspelled
+/// field of the table form is an error naming it instead of being ignored.
+impl<'de> serde::Deserialize<'de> for Owners {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::value::{MapAccessDeserializer, SeqAccessDeserializer};
+        use serde::de::{MapAccess, SeqAccess};
+
+        #[derive(serde::Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct Table {
+            reviewers: Vec<String>,
+            #[serde(default = "default_min_reviewers")]
+            min_reviewers: usize,
+        }
+
+        struct Visitor;
+
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = Owners;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a list of reviewers or a table with `reviewers`")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Owners, A::Error> {
+                serde::Deserialize::deserialize(SeqAccessDeserializer::new(seq))
+                    .map(Owners::Reviewers)
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Owners, A::Error> {
+                let Table {
+                    reviewers,
+                    min_reviewers,
+                } = serde::Deserialize::deserialize(MapAccessDeserializer::new(map))?;
+                Ok(Owners::Table {
+                    reviewers,
+                    min_reviewers,
+                })
+            }
+        }
+
+        deserializer.deserialize_any(Visitor)
+    }
+}
+
+impl Owners {
+    pub(crate) fn reviewers(&self) -> &[String] {
+        match self {
+            Owners::Reviewers(reviewers) | Owners::Table { reviewers, .. } => reviewers,
+        }
+    }
+
+    pub(crate) fn min_reviewers(&self) -> usize {
+        match self {
+            Owners::Reviewers(_) => 1,
+            Owners::Table { min_reviewers, .. } => *min_reviewers,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Default, Clone, Copy, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum ReviewMode {
+    /// Assign the reviewer to the PR.
+    #[default]
+    Assignee,
+    /// Request a review from the reviewer, for repositories that use GitHub's
+    /// review requests (for example, with required reviewers in branch
+    /// protection).
+    ReviewRequest,
+    /// Both assign the reviewer and request a review from them.
+    Both,
+}
+
+impl ReviewMode {
+    pub(crate) fn assigns(self) -> bool {
+        matches!(self, ReviewMode::Assignee | ReviewMode::Both)
+    }
+
+    pub(crate) fn requests_review(self) -> bool {
+        matches!(self, ReviewMode::ReviewRequest | ReviewMode::Both)
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct StaleReviewConfig {
+    /// Ping the reviewer after this many days without a comment or review
+    /// from them.
+    pub(crate) ping_after_days: u32,
+    /// Assign another reviewer after this many days without a comment or
+    /// review. Reviewers are only reassigned after being pinged.
+    pub(crate) reassign_after_days: Option<u32>,
+    /// Only PRs with this label are considered to be waiting on the
+    /// reviewer.
+    #[serde(default = "StaleReviewConfig::default_label")]
+    pub(crate) label: String,
+}
+
+impl StaleReviewConfig {
+    fn default_label() -> String {
+        "S-waiting-on-review".to_string()
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Default, Clone, Copy, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum ReviewerSelection {
+    /// Pick a candidate at random.
+    #[default]
+    Random,
+    /// Pick the candidate who was least recently assigned a PR.
+    RoundRobin,
+    /// Pick the candidate with the fewest assigned open PRs, then the fewest
+    /// PRs assigned in the last 30 days.
+    LeastLoaded,
 }
 
 impl AssignConfig {
@@ -101,9 +256,16 @@ impl AssignConfig {
             .iter()
             .any(|vacationer| name_lower == vacationer.to_lowercase())
     }
+
+    /// Whether reviewers can be found from the modified files, with `owners`
+    /// or `codeowners`.
+    pub(crate) fn has_owners(&self) -> bool {
+        !self.owners.is_empty() || self.codeowners
+    }
 }
 
 #[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
 pub(crate) struct NoMergesConfig {
     /// No action will be taken on PRs with these labels.
     #[serde(default)]
@@ -120,6 +282,7 @@ pub(crate) struct NoMergesConfig {
 }
 
 #[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
 pub(crate) struct NoteConfig {
     #[serde(default)]
     _empty: (),
@@ -132,6 +295,7 @@ pub(crate) struct MentionsConfig {
 }
 
 #[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
 pub(crate) struct MentionsPathConfig {
     pub(crate) message: Option<String>,
     #[serde(default)]
@@ -139,7 +303,22 @@ pub(crate) struct MentionsPathConfig {
 }
 
 #[derive(PartialEq, Eq, Debug, serde::Deserialize)]
-#[serde(rename_all = "kebab-case")]
+pub(crate) struct RequiredReviewsConfig {
+    /// The key is a path prefix, like in `mentions`.
+    #[serde(flatten)]
+    pub(crate) paths: HashMap<String, RequiredReviewPathConfig>,
+}
+
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RequiredReviewPathConfig {
+    /// Users, rust-lang teams, or `assign.adhoc_groups`, one of whom must
+    /// approve PRs that modify the path.
+    pub(crate) reviewers: Vec<String>,
+}
+
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub(crate) struct RelabelConfig {
     #[serde(default)]
     pub(crate) allow_unauthenticated: Vec<String>,
@@ -147,11 +326,126 @@ pub(crate) struct RelabelConfig {
 
 #[derive(PartialEq, Eq, Debug, serde::Deserialize)]
 pub(crate) struct ShortcutConfig {
+    // shortcut name -> shortcut
+    // An empty `[shortcut]` section uses the default shortcuts.
+    #[serde(flatten)]
+    shortcuts: HashMap<String, ShortcutEntryConfig>,
+}
+
+lazy_static::lazy_static! {
+    static ref DEFAULT_SHORTCUTS: HashMap<String, ShortcutEntryConfig> = {
+        let shortcut = |label: &str, alias: &[&str]| ShortcutEntryConfig {
+            label: label.to_string(),
+            alias: alias.iter().map(|a| a.to_string()).collect(),
+            exclusive_labels: None,
+            pull_requests: true,
+            issues: false,
+            reviewer_action: None,
+        };
+        HashMap::from([
+            ("ready".to_string(), shortcut("S-waiting-on-review", &["review", "reviewer"])),
+            ("author".to_string(), shortcut("S-waiting-on-author", &[])),
+            ("blocked".to_string(), shortcut("S-blocked", &[])),
+        ])
+    };
+}
+
+impl ShortcutConfig {
+    /// The configured shortcuts, or the default ones if there are none.
+    pub(crate) fn shortcuts(&self) -> &HashMap<String, ShortcutEntryConfig> {
+        if self.shortcuts.is_empty() {
+            &DEFAULT_SHORTCUTS
+        } else {
+            &self.shortcuts
+        }
+    }
+
+    /// The names and aliases of all shortcuts, for the command parser.
+    pub(crate) fn names(&self) -> Vec<String> {
+        Self::names_of(self.shortcuts())
+    }
+
+    /// The names and aliases of the default shortcuts.
+    pub(crate) fn default_names() -> Vec<String> {
+        Self::names_of(&DEFAULT_SHORTCUTS)
+    }
+
+    fn names_of(shortcuts: &HashMap<String, ShortcutEntryConfig>) -> Vec<String> {
+        shortcuts
+            .iter()
+            .flat_map(|(name, cfg)| std::iter::once(name).chain(&cfg.alias))
+            .cloned()
+            .collect()
+    }
+
+    pub(crate) fn get_by_name(&self, name: &str) -> Option<(&str, &ShortcutEntryConfig)> {
+        let shortcuts = self.shortcuts();
+        if let Some((name, cfg)) = shortcuts.get_key_value(name) {
+            return Some((name, cfg));
+        }
+
+        for (shortcut, cfg) in shortcuts.iter() {
+            if cfg.alias.contains(name) {
+                return Some((shortcut, cfg));
+            }
+        }
+
+        None
+    }
+
+    /// The labels to remove when the given shortcut is used.
+    pub(crate) fn exclusive_labels<'a>(&'a self, cfg: &'a ShortcutEntryConfig) -> Vec<&'a str> {
+        match &cfg.exclusive_labels {
+            Some(labels) => labels.iter().map(|l| l.as_str()).collect(),
+            None => self
+                .shortcuts()
+                .values()
+                .map(|other| other.label.as_str())
+                .filter(|label| *label != cfg.label)
+                .collect(),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ShortcutEntryConfig {
+    /// The label to add.
+    pub(crate) label: String,
     #[serde(default)]
-    _empty: (),
+    pub(crate) alias: HashSet<String>,
+    /// Labels to remove when adding `label`. Defaults to the labels of all
+    /// other shortcuts, so that they are mutually exclusive.
+    pub(crate) exclusive_labels: Option<Vec<String>>,
+    /// Whether the shortcut can be used on pull requests.
+    #[serde(default = "ShortcutEntryConfig::pull_requests_default")]
+    pub(crate) pull_requests: bool,
+    /// Whether the shortcut can be used on issues.
+    #[serde(default)]
+    pub(crate) issues: bool,
+    /// What to do with the reviewers of a pull request when the shortcut is
+    /// used.
+    pub(crate) reviewer_action: Option<ShortcutReviewerAction>,
+}
+
+impl ShortcutEntryConfig {
+    fn pull_requests_default() -> bool {
+        true
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum ShortcutReviewerAction {
+    /// Request another review from everyone who has reviewed the pull
+    /// request.
+    RerequestReview,
+    /// Assign the pull request to everyone who has reviewed it.
+    Reassign,
 }
 
 #[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
 pub(crate) struct PrioritizeConfig {
     pub(crate) label: String,
 }
@@ -175,6 +469,7 @@ impl AutolabelConfig {
 }
 
 #[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
 pub(crate) struct AutolabelLabelConfig {
     #[serde(default)]
     pub(crate) trigger_labels: Vec<String>,
@@ -195,6 +490,7 @@ pub(crate) struct NotifyZulipConfig {
 }
 
 #[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
 pub(crate) struct NotifyZulipLabelConfig {
     pub(crate) zulip_stream: u64,
     pub(crate) topic: String,
@@ -207,6 +503,7 @@ pub(crate) struct NotifyZulipLabelConfig {
 }
 
 #[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
 pub(crate) struct MajorChangeConfig {
     /// A username (typically a group, e.g. T-lang) to ping on Zulip for newly
     /// opened proposals.
@@ -217,14 +514,16 @@ pub(crate) struct MajorChangeConfig {
     #[serde(default = "MajorChangeConfig::enabling_label_default")]
     pub(crate) enabling_label: String,
     /// This is the label applied when issuing a `@rustbot second` command, it
-    /// indicates that the proposal has moved into the 10 day waiting period.
+    /// indicates that the proposal has moved into the waiting period.
     pub(crate) second_label: String,
     /// This is the label applied after the waiting period has successfully
-    /// elapsed (currently not automatically applied; this must be done
-    /// manually).
+    /// elapsed, replacing `second_label`.
     // This has a default primarily for backwards compatibility.
     #[serde(default = "MajorChangeConfig::accept_label_default")]
     pub(crate) accept_label: String,
+    /// The number of days after seconding before a proposal is accepted.
+    #[serde(default = "MajorChangeConfig::waiting_period_days_default")]
+    pub(crate) waiting_period_days: u32,
     /// This is the label to be added to newly opened proposals, so they can be
     /// discussed in a meeting.
     pub(crate) meeting_label: String,
@@ -239,18 +538,46 @@ impl MajorChangeConfig {
     fn accept_label_default() -> String {
         String::from("major-change-accepted")
     }
+    fn waiting_period_days_default() -> u32 {
+        10
+    }
 }
 
 #[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
 pub(crate) struct GlacierConfig {}
 
 #[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
 pub(crate) struct CloseConfig {}
 
 #[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct NotificationConfig {
+    /// Users to notify of every newly opened issue and pull request, as if
+    /// they had been mentioned in it.
+    #[serde(default)]
+    pub(crate) mention_on_open: Vec<String>,
+}
+
+/// Disables all changes to issues and pull requests in the repository, see
+/// [`crate::dry_run`].
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct DryRunConfig {}
+
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
 pub(crate) struct ReviewSubmittedConfig {
     pub(crate) review_labels: Vec<String>,
     pub(crate) reviewed_label: String,
+    /// Put the PR back in the review queue when the author pushes new commits.
+    #[serde(default)]
+    pub(crate) requeue_on_push: bool,
+    /// Put the PR back in the review queue when the author re-requests a
+    /// review.
+    #[serde(default)]
+    pub(crate) requeue_on_review_request: bool,
 }
 
 pub(crate) async fn get(
@@ -262,7 +589,7 @@ pub(crate) async fn get(
         config
     } else {
         log::trace!("fetching fresh config for {}", repo.full_name);
-        let res = get_fresh_config(gh, repo).await;
+        let res = get_fresh_config(gh, repo, &repo.default_branch).await;
         CONFIG_CACHE
             .write()
             .unwrap()
@@ -271,8 +598,85 @@ pub(crate) async fn get(
     }
 }
 
+/// Updates the cache if a push to the default branch of a repository may
+/// have changed its configuration.
+///
+/// The configuration is re-fetched at the pushed commit, since the raw file
+/// server may still be serving the previous version of the branch. A push to
+/// an organization's `.github` repository drops the cached configuration of
+/// every repository in the organization instead.
+pub(crate) async fn handle_push(gh: &GithubClient, push: &PushEvent) {
+    let repo = push.repository();
+    if push.git_ref != format!("refs/heads/{}", repo.default_branch) {
+        return;
+    }
+    // GitHub only includes up to 20 commits in the payload, so assume the
+    // file was changed if there may be more.
+    let touched = push.commits.len() >= 20
+        || push.commits.iter().any(|commit| {
+            commit
+                .added
+                .iter()
+                .chain(&commit.modified)
+                .chain(&commit.removed)
+                .any(|path| path == CONFIG_FILE_NAME)
+        });
+    if !touched {
+        return;
+    }
+
+    if repo.name() == ORG_CONFIG_REPO {
+        log::info!("dropping cached config for {}", repo.owner());
+        let prefix = format!("{}/", repo.owner());
+        CONFIG_CACHE
+            .write()
+            .unwrap()
+            .retain(|name, _| !name.starts_with(&prefix));
+        return;
+    }
+
+    log::info!("refreshing config for {} at {}", repo.full_name, push.after);
+    let res = get_fresh_config(gh, repo, &push.after).await;
+    if let Err(e) = &res {
+        log::warn!("configuration error {}: {e}", repo.full_name);
+    }
+    CONFIG_CACHE
+        .write()
+        .unwrap()
+        .insert(repo.full_name.to_string(), (res, Instant::now()));
+}
+
+/// A configuration in the cache, for debugging.
+#[derive(Debug, serde::Serialize)]
+pub struct CachedConfig {
+    pub repo: String,
+    pub age_secs: u64,
+    /// Whether the configuration will be re-fetched on next use.
+    pub expired: bool,
+    /// The configuration, in its `Debug` representation.
+    pub config: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Returns every configuration in the cache, sorted by repository.
+pub fn cached_configs() -> Vec<CachedConfig> {
+    let cache = CONFIG_CACHE.read().unwrap();
+    let mut configs: Vec<_> = cache
+        .iter()
+        .map(|(repo, (config, fetch_time))| CachedConfig {
+            repo: repo.clone(),
+            age_secs: fetch_time.elapsed().as_secs(),
+            expired: fetch_time.elapsed() >= REFRESH_EVERY,
+            config: config.as_ref().ok().map(|config| format!("{config:#?}")),
+            error: config.as_ref().err().map(|e| e.to_string()),
+        })
+        .collect();
+    configs.sort_by(|a, b| a.repo.cmp(&b.repo));
+    configs
+}
+
 #[derive(PartialEq, Eq, Debug, serde::Deserialize)]
-#[serde(rename_all = "kebab-case")]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub(crate) struct GitHubReleasesConfig {
     pub(crate) format: ChangelogFormat,
     pub(crate) project_name: String,
@@ -291,24 +695,156 @@ fn get_cached_config(repo: &str) -> Option<Result<Arc<Config>, ConfigurationErro
     })
 }
 
+/// Returns whether the configuration cached for the given repository has
+/// dry-run mode enabled, regardless of how old it is.
+pub(crate) fn cached_dry_run(repo: &str) -> bool {
+    let cache = CONFIG_CACHE.read().unwrap();
+    matches!(
+        cache.get(repo),
+        Some((Ok(config), _)) if config.dry_run.is_some()
+    )
+}
+
+/// Fetches the configuration of a repository at the given branch or commit.
+///
+/// If the organization has a `triagebot.toml` in the default branch of its
+/// `.github` repository, the repository's own `triagebot.toml` is layered
+/// over it (see [`merge_config`]). Repositories without a `triagebot.toml` use
+/// the organization's configuration as is.
 async fn get_fresh_config(
     gh: &GithubClient,
     repo: &Repository,
+    git_ref: &str,
 ) -> Result<Arc<Config>, ConfigurationError> {
     let contents = gh
-        .raw_file(&repo.full_name, &repo.default_branch, CONFIG_FILE_NAME)
+        .raw_file(&repo.full_name, git_ref, CONFIG_FILE_NAME)
         .await
-        .map_err(|e| ConfigurationError::Http(Arc::new(e)))?
-        .ok_or(ConfigurationError::Missing)?;
-    let config = Arc::new(toml::from_slice::<Config>(&contents).map_err(ConfigurationError::Toml)?);
+        .map_err(|e| ConfigurationError::Http(Arc::new(e)))?;
+    let org_repo = format!("{}/{}", repo.owner(), ORG_CONFIG_REPO);
+    let org_contents = if repo.name() == ORG_CONFIG_REPO {
+        None
+    } else {
+        gh.raw_file(&org_repo, "HEAD", CONFIG_FILE_NAME)
+            .await
+            .map_err(|e| ConfigurationError::Http(Arc::new(e)))?
+    };
+    let config = match (org_contents, contents) {
+        (None, None) => return Err(ConfigurationError::Missing),
+        (None, Some(contents)) => parse_config(&contents).map_err(ConfigurationError::Toml),
+        (Some(org_contents), contents) => {
+            parse_layered_config(&org_repo, &org_contents, contents.as_deref())
+        }
+    };
+    let config = Arc::new(config?);
     log::debug!("fresh configuration for {}: {:?}", repo.full_name, config);
     Ok(config)
 }
 
+/// Parses the `triagebot.toml` of the organization repository `org_repo`,
+/// with a repository's `triagebot.toml` layered over it.
+///
+/// The organization's file must be a valid configuration on its own, so that
+/// its errors are reported against it rather than every repository using it.
+/// Errors of the merged configuration therefore come from the repository's
+/// file, which is parsed again on its own to report them with their line and
+/// column. That parse fails on any `false` removing part of the
+/// organization's configuration, in which case the merged error is reported.
+fn parse_layered_config(
+    org_repo: &str,
+    org: &[u8],
+    repo: Option<&[u8]>,
+) -> Result<Config, ConfigurationError> {
+    let org_error = |e| ConfigurationError::OrgToml(org_repo.to_string(), e);
+    let mut config: toml::value::Table = toml::from_slice(org).map_err(org_error)?;
+    parse_config(org).map_err(org_error)?;
+    if let Some(repo) = repo {
+        let overlay = toml::from_slice(repo).map_err(ConfigurationError::Toml)?;
+        merge_config(&mut config, overlay);
+    }
+    toml::Value::Table(config)
+        .try_into()
+        .map_err(|e| match repo.map(parse_config) {
+            Some(Err(repo_error))
+                if !repo_error
+                    .to_string()
+                    .starts_with("invalid type: boolean `false`") =>
+            {
+                ConfigurationError::Toml(repo_error)
+            }
+            _ => ConfigurationError::Toml(e),
+        })
+}
+
+/// How a section of a repository's `triagebot.toml` is combined with the same
+/// section of its organization's.
+#[derive(Debug, PartialEq, Eq)]
+enum SectionMerge {
+    /// The repository's section replaces the organization's.
+    Replace,
+    /// Each field set by the repository replaces the organization's, so that
+    /// for example `[assign]` can override `owners` but keep `adhoc_groups`.
+    Fields,
+    /// The section is a map of independent entries (teams, labels, paths...),
+    /// and each entry set by the repository replaces the organization's.
+    Entries,
+}
+
+fn section_merge(section: &str) -> SectionMerge {
+    match section {
+        "assign" => SectionMerge::Fields,
+        "autolabel" | "mentions" | "notify-zulip" | "ping" | "required-reviews" | "shortcut" => {
+            SectionMerge::Entries
+        }
+        _ => SectionMerge::Replace,
+    }
+}
+
+/// Merges a repository's configuration into its organization's, following the
+/// rules of [`section_merge`].
+///
+/// Setting a section, field or entry to `false` in the repository removes the
+/// organization's one, e.g. `assign = false` or `ping.compiler = false`. For
+/// the fields of `[assign]`, whose flags all default to `false`, this is the
+/// same as setting them.
+fn merge_config(base: &mut toml::value::Table, overlay: toml::value::Table) {
+    for (section, value) in overlay {
+        let merge = section_merge(&section);
+        match (merge, base.get_mut(&section), value) {
+            (_, _, toml::Value::Boolean(false)) => {
+                base.remove(&section);
+            }
+            (
+                SectionMerge::Fields | SectionMerge::Entries,
+                Some(toml::Value::Table(base)),
+                toml::Value::Table(overlay),
+            ) => {
+                for (key, value) in overlay {
+                    if value == toml::Value::Boolean(false) {
+                        base.remove(&key);
+                    } else {
+                        base.insert(key, value);
+                    }
+                }
+            }
+            (_, _, value) => {
+                base.insert(section, value);
+            }
+        }
+    }
+}
+
+/// Parses the contents of a `triagebot.toml`.
+pub(crate) fn parse_config(contents: &[u8]) -> Result<Config, toml::de::Error> {
+    toml::from_slice(contents)
+}
+
 #[derive(Clone, Debug)]
 pub enum ConfigurationError {
     Missing,
     Toml(toml::de::Error),
+    /// The `triagebot.toml` of the organization's `.github` repository, named
+    /// here, is malformed.
+    OrgToml(String, toml::de::Error),
     Http(Arc<anyhow::Error>),
 }
 
@@ -323,7 +859,18 @@ impl fmt::Display for ConfigurationError {
                  Add a `triagebot.toml` in the root of the default branch to enable it."
             ),
             ConfigurationError::Toml(e) => {
-                write!(f, "Malformed `triagebot.toml` in default branch.\n{e}")
+                write!(
+                    f,
+                    "Malformed `triagebot.toml` in default branch.\n{}",
+                    describe_toml_error(e)
+                )
+            }
+            ConfigurationError::OrgToml(repo, e) => {
+                write!(
+                    f,
+                    "Malformed `triagebot.toml` in default branch of `{repo}`.\n{}",
+                    describe_toml_error(e)
+                )
             }
             ConfigurationError::Http(e) => {
                 write!(
@@ -335,6 +882,53 @@ impl fmt::Display for ConfigurationError {
     }
 }
 
+/// Formats a TOML error, adding a suggestion if it is about an unknown field
+/// or variant that looks like a misspelling of an expected one.
+pub(crate) fn describe_toml_error(e: &toml::de::Error) -> String {
+    let message = e.to_string();
+    match suggestion(&message) {
+        Some(expected) => format!("{message}\nDid you mean `{expected}`?"),
+        None => message,
+    }
+}
+
+/// Finds the closest expected name in a serde error message such as
+/// "unknown field `autolable`, expected one of `assign`, `autolabel`, ...".
+fn suggestion(message: &str) -> Option<&str> {
+    let rest = message
+        .strip_prefix("unknown field `")
+        .or_else(|| message.strip_prefix("unknown variant `"))?;
+    let (unknown, rest) = rest.split_once('`')?;
+    // The toml error may be followed by the key and location of the error.
+    let expected = rest.strip_prefix(", expected ")?;
+    let expected = expected.split(" for key ").next()?;
+    let expected = expected.split(" at line ").next()?;
+    let normalize = |name: &str| name.to_lowercase().replace('-', "_");
+    let unknown = normalize(unknown);
+    let (distance, closest) = expected
+        .split('`')
+        .skip(1)
+        .step_by(2)
+        .map(|name| (edit_distance(&unknown, &normalize(name)), name))
+        .min()?;
+    (distance <= std::cmp::max(1, unknown.chars().count() / 3)).then_some(closest)
+}
+
+/// The Levenshtein distance between two strings.
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != *cb);
+            current.push(substitution.min(prev[j + 1] + 1).min(current[j] + 1));
+        }
+        prev = current;
+    }
+    prev[b.len()]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -405,14 +999,20 @@ mod tests {
                     contributing_url: None,
                     adhoc_groups: HashMap::new(),
                     owners: HashMap::new(),
+                    codeowners: false,
                     users_on_vacation: HashSet::from(["jyn514".into()]),
+                    selection: ReviewerSelection::Random,
+                    stale_review: None,
+                    review_mode: ReviewMode::Assignee,
                 }),
                 note: Some(NoteConfig { _empty: () }),
                 ping: Some(PingConfig { teams: ping_teams }),
                 nominate: Some(NominateConfig {
                     teams: nominate_teams
                 }),
-                shortcut: Some(ShortcutConfig { _empty: () }),
+                shortcut: Some(ShortcutConfig {
+                    shortcuts: HashMap::new()
+                }),
                 prioritize: None,
                 major_change: None,
                 glacier: None,
@@ -423,7 +1023,366 @@ mod tests {
                 review_submitted: None,
                 mentions: None,
                 no_merges: None,
+                dry_run: None,
+                notification: None,
+                required_reviews: None,
             }
         );
     }
+
+    fn toml_error(config: &str) -> String {
+        let e = parse_config(config.as_bytes()).unwrap_err();
+        ConfigurationError::Toml(e).to_string()
+    }
+
+    #[test]
+    fn unknown_section() {
+        let error = toml_error(
+            r#"
+            [autolable."A-diagnostics"]
+            trigger_files = ["compiler/rustc_errors"]
+            "#,
+        );
+        assert!(error.contains("unknown field `autolable`"), "{error}");
+        assert!(error.ends_with("Did you mean `autolabel`?"), "{error}");
+    }
+
+    #[test]
+    fn unknown_field() {
+        let error = toml_error(
+            r#"
+            [autolabel."A-diagnostics"]
+            trigger-files = ["compiler/rustc_errors"]
+            "#,
+        );
+        assert!(error.contains("unknown field `trigger-files`"), "{error}");
+        assert!(error.ends_with("Did you mean `trigger_files`?"), "{error}");
+
+        let error = toml_error(
+            r#"
+            [assign]
+            users-on-vacation = ["jyn514"]
+            "#,
+        );
+        assert!(
+            error.ends_with("Did you mean `users_on_vacation`?"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn owners_table() {
+        let config = parse_config(
+            br#"
+            [assign.owners]
+            "/compiler" = ["compiler"]
+            "/library" = { reviewers = ["libs"], min_reviewers = 2 }
+            "/src" = { reviewers = ["bootstrap"] }
+            "#,
+        )
+        .unwrap();
+        let owners = config.assign.unwrap().owners;
+        assert_eq!(
+            owners["/compiler"],
+            Owners::Reviewers(vec!["compiler".into()])
+        );
+        assert_eq!(owners["/library"].min_reviewers(), 2);
+        assert_eq!(owners["/src"].min_reviewers(), 1);
+
+        let error = toml_error(
+            r#"
+            [assign.owners]
+            "/library" = { reviewers = ["libs"], min_reviewer = 2 }
+            "#,
+        );
+        assert!(error.contains("unknown field `min_reviewer`"), "{error}");
+        assert!(error.ends_with("Did you mean `min_reviewers`?"), "{error}");
+
+        let error = toml_error(
+            r#"
+            [assign.owners]
+            "/library" = "libs"
+            "#,
+        );
+        assert!(
+            error.contains("a list of reviewers or a table with `reviewers`"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn unknown_field_without_suggestion() {
+        let error = toml_error(
+            r#"
+            [prioritize]
+            label = "I-prioritize"
+            zulip_stream = 1
+            "#,
+        );
+        assert!(error.contains("unknown field `zulip_stream`"), "{error}");
+        assert!(!error.contains("Did you mean"), "{error}");
+    }
+
+    #[test]
+    fn layered() {
+        let org = r#"
+            [relabel]
+            allow-unauthenticated = ["A-*", "C-*"]
+
+            [ping.compiler]
+            message = "Compiler team"
+
+            [notification]
+            mention_on_open = ["dtolnay"]
+        "#;
+        let repo = r#"
+            [relabel]
+            allow-unauthenticated = ["T-*"]
+
+            [ping.lang]
+            message = "Lang team"
+        "#;
+        let config =
+            parse_layered_config("rust-lang/.github", org.as_bytes(), Some(repo.as_bytes()))
+                .unwrap();
+        assert_eq!(
+            config.relabel,
+            Some(RelabelConfig {
+                allow_unauthenticated: vec!["T-*".into()],
+            })
+        );
+        let mut ping_teams: Vec<_> = config.ping.unwrap().teams.into_keys().collect();
+        ping_teams.sort();
+        assert_eq!(ping_teams, ["compiler", "lang"]);
+        assert_eq!(
+            config.notification,
+            Some(NotificationConfig {
+                mention_on_open: vec!["dtolnay".into()],
+            })
+        );
+
+        let config = parse_layered_config("rust-lang/.github", org.as_bytes(), None).unwrap();
+        assert_eq!(
+            config.relabel,
+            Some(RelabelConfig {
+                allow_unauthenticated: vec!["A-*".into(), "C-*".into()],
+            })
+        );
+    }
+
+    #[test]
+    fn layered_assign() {
+        let org = r#"
+            [assign]
+            warn_non_default_branch = true
+            users_on_vacation = ["jyn514"]
+
+            [assign.adhoc_groups]
+            compiler = ["@alice"]
+
+            [assign.owners]
+            "/compiler" = ["compiler"]
+            "/library" = ["@bob"]
+
+            [assign.stale_review]
+            ping_after_days = 14
+        "#;
+
+        // A repository's `owners` replaces the organization's, rather than
+        // being merged into it, and the other fields are kept.
+        let repo = r#"
+            [assign.owners]
+            "/src" = ["compiler"]
+        "#;
+        let config =
+            parse_layered_config("rust-lang/.github", org.as_bytes(), Some(repo.as_bytes()))
+                .unwrap();
+        let assign = config.assign.unwrap();
+        let owners: Vec<_> = assign.owners.keys().collect();
+        assert_eq!(owners, ["/src"]);
+        assert!(assign.adhoc_groups.contains_key("compiler"));
+        assert!(assign.warn_non_default_branch);
+        assert!(assign.stale_review.is_some());
+
+        let repo = r#"
+            [assign]
+            owners = {}
+            stale_review = false
+            warn_non_default_branch = false
+        "#;
+        let config =
+            parse_layered_config("rust-lang/.github", org.as_bytes(), Some(repo.as_bytes()))
+                .unwrap();
+        let assign = config.assign.unwrap();
+        assert!(assign.owners.is_empty());
+        assert!(assign.stale_review.is_none());
+        assert!(!assign.warn_non_default_branch);
+        assert_eq!(assign.users_on_vacation, HashSet::from(["jyn514".into()]));
+
+        let repo = "assign = false";
+        let config =
+            parse_layered_config("rust-lang/.github", org.as_bytes(), Some(repo.as_bytes()))
+                .unwrap();
+        assert!(config.assign.is_none());
+    }
+
+    #[test]
+    fn layered_entries() {
+        let org = r#"
+            [ping.compiler]
+            message = "Compiler team"
+            label = "T-compiler"
+
+            [ping.lang]
+            message = "Lang team"
+
+            [autolabel."A-diagnostics"]
+            trigger_files = ["compiler/rustc_errors"]
+            new_pr = true
+        "#;
+        let repo = r#"
+            [ping]
+            lang = false
+
+            [ping.compiler]
+            message = "Our compiler team"
+
+            [autolabel."A-diagnostics"]
+            trigger_labels = ["D-*"]
+        "#;
+        let config =
+            parse_layered_config("rust-lang/.github", org.as_bytes(), Some(repo.as_bytes()))
+                .unwrap();
+        // Entries are replaced as a whole, so the organization's `label` of
+        // `ping.compiler` is gone.
+        let ping = config.ping.unwrap();
+        let ping_teams: Vec<_> = ping.teams.keys().collect();
+        assert_eq!(ping_teams, ["compiler"]);
+        assert_eq!(
+            ping.teams["compiler"],
+            PingTeamConfig {
+                message: "Our compiler team".into(),
+                alias: HashSet::new(),
+                label: None,
+            }
+        );
+        let autolabel = config.autolabel.unwrap();
+        let diagnostics = &autolabel.labels["A-diagnostics"];
+        assert_eq!(diagnostics.trigger_labels, ["D-*"]);
+        assert!(diagnostics.trigger_files.is_empty());
+        assert!(!diagnostics.new_pr);
+    }
+
+    #[test]
+    fn layered_errors() {
+        let error = parse_layered_config(
+            "rust-lang/.github",
+            b"[autolable.\"A-diagnostics\"]",
+            Some(b"[note]"),
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(
+            error.starts_with(
+                "Malformed `triagebot.toml` in default branch of `rust-lang/.github`."
+            ),
+            "{error}"
+        );
+
+        let error = parse_layered_config(
+            "rust-lang/.github",
+            b"[note]",
+            Some(b"[autolable.\"A-diagnostics\"]"),
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(
+            error.starts_with("Malformed `triagebot.toml` in default branch.\n"),
+            "{error}"
+        );
+        assert!(error.contains("at line 1 column 1"), "{error}");
+        assert!(error.ends_with("Did you mean `autolabel`?"), "{error}");
+
+        // Errors of the merged configuration are located in the repository's
+        // file.
+        let org = r#"
+            [ping.lang]
+            message = "Lang team"
+
+            [assign]
+        "#;
+        let repo = r#"
+            [assign]
+            users-on-vacation = ["jyn514"]
+        "#;
+        let error =
+            parse_layered_config("rust-lang/.github", org.as_bytes(), Some(repo.as_bytes()))
+                .unwrap_err()
+                .to_string();
+        assert!(
+            error.contains("unknown field `users-on-vacation`"),
+            "{error}"
+        );
+        assert!(error.contains("at line 2 column 13"), "{error}");
+
+        // Removing part of the organization's configuration doesn't hide the
+        // error, even though it can't be located.
+        let repo = r#"
+            ping = false
+
+            [assign]
+            users-on-vacation = ["jyn514"]
+        "#;
+        let error =
+            parse_layered_config("rust-lang/.github", org.as_bytes(), Some(repo.as_bytes()))
+                .unwrap_err()
+                .to_string();
+        assert!(
+            error.contains("unknown field `users-on-vacation`"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn shortcuts() {
+        let config = r#"
+            [shortcut.ready]
+            label = "S-review"
+            alias = ["review"]
+            reviewer_action = "rerequest-review"
+
+            [shortcut.author]
+            label = "S-author"
+
+            [shortcut.triaged]
+            label = "S-triaged"
+            exclusive_labels = ["S-needs-triage"]
+            pull_requests = false
+            issues = true
+        "#;
+        let config = toml::from_str::<Config>(&config).unwrap();
+        let shortcut = config.shortcut.unwrap();
+        let (name, ready) = shortcut.get_by_name("review").unwrap();
+        assert_eq!(name, "ready");
+        assert_eq!(
+            ready.reviewer_action,
+            Some(ShortcutReviewerAction::RerequestReview)
+        );
+        let mut exclusive = shortcut.exclusive_labels(ready);
+        exclusive.sort();
+        assert_eq!(exclusive, ["S-author", "S-triaged"]);
+        let (_, triaged) = shortcut.get_by_name("triaged").unwrap();
+        assert_eq!(shortcut.exclusive_labels(triaged), ["S-needs-triage"]);
+        assert!(shortcut.get_by_name("blocked").is_none());
+
+        let config = toml::from_str::<Config>("[shortcut]").unwrap();
+        let shortcut = config.shortcut.unwrap();
+        let mut names = shortcut.names();
+        names.sort();
+        assert_eq!(names, ["author", "blocked", "ready", "review", "reviewer"]);
+        assert_eq!(
+            shortcut.get_by_name("blocked").unwrap().1.label,
+            "S-blocked"
+        );
+    }
 }
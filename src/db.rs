@@ -1,4 +1,5 @@
 use crate::handlers::jobs::handle_job;
+use crate::jobs::retry_policy;
 use crate::{db::jobs::*, handlers::Context};
 use anyhow::Context as _;
 use chrono::Utc;
@@ -8,9 +9,12 @@ use std::sync::{Arc, Mutex};
 use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 use tokio_postgres::Client as DbClient;
 
+pub mod events;
 pub mod issue_data;
 pub mod jobs;
 pub mod notifications;
+pub mod review_assignments;
+pub mod review_prefs;
 pub mod rustc_commits;
 
 const CERT_URL: &str = "https://s3.amazonaws.com/rds-downloads/rds-ca-2019-root.pem";
@@ -68,6 +72,13 @@ impl ClientPool {
     }
 
     pub async fn get(&self) -> PooledClient {
+        self.try_get().await.unwrap()
+    }
+
+    /// Like `get`, but returns an error instead of panicking if a new
+    /// connection can't be made, for callers that can do without the
+    /// database.
+    pub async fn try_get(&self) -> anyhow::Result<PooledClient> {
         let permit = self.permits.clone().acquire_owned().await.unwrap();
         {
             let mut slots = self.connections.lock().unwrap_or_else(|e| e.into_inner());
@@ -75,25 +86,25 @@ impl ClientPool {
             // "possibly open" connections left).
             while let Some(c) = slots.pop() {
                 if !c.is_closed() {
-                    return PooledClient {
+                    return Ok(PooledClient {
                         client: Some(c),
                         permit,
                         pool: self.connections.clone(),
-                    };
+                    });
                 }
             }
         }
 
-        PooledClient {
-            client: Some(make_client().await.unwrap()),
+        Ok(PooledClient {
+            client: Some(make_client().await?),
             permit,
             pool: self.connections.clone(),
-        }
+        })
     }
 }
 
 async fn make_client() -> anyhow::Result<tokio_postgres::Client> {
-    let db_url = std::env::var("DATABASE_URL").expect("needs DATABASE_URL");
+    let db_url = std::env::var("DATABASE_URL").context("needs DATABASE_URL")?;
     if db_url.contains("rds.amazonaws.com") {
         let cert = &CERTIFICATE_PEM[..];
         let cert = Certificate::from_pem(&cert).context("made certificate")?;
@@ -188,31 +199,48 @@ pub async fn schedule_jobs(db: &DbClient, jobs: Vec<JobSchedule>) -> anyhow::Res
         let mut upcoming = job.schedule.upcoming(Utc).take(1);
 
         if let Some(scheduled_at) = upcoming.next() {
-            if let Err(_) = get_job_by_name_and_scheduled_at(&db, &job.name, &scheduled_at).await {
-                // mean there's no job already in the db with that name and scheduled_at
-                insert_job(&db, &job.name, &scheduled_at, &job.metadata).await?;
-            }
+            // This does nothing if the job is already in the db.
+            insert_job(db, &job.name, &scheduled_at, &job.metadata).await?;
         }
     }
 
+    delete_old_executions(db).await?;
+
     Ok(())
 }
 
+/// Runs the jobs that are due, one at a time, until there are none left.
+///
+/// Several instances of triagebot can do this at the same time: each job is
+/// claimed by a single instance, which renews its lease while the job runs.
 pub async fn run_scheduled_jobs(ctx: &Context, db: &DbClient) -> anyhow::Result<()> {
-    let jobs = get_jobs_to_execute(&db).await.unwrap();
-    tracing::trace!("jobs to execute: {:#?}", jobs);
-
-    for job in jobs.iter() {
-        update_job_executed_at(&db, &job.id).await?;
+    while let Some(job) = claim_job(db).await? {
+        tracing::trace!("job to execute: {:#?}", job);
+        let execution_id = start_execution(db, &job).await?;
 
-        match handle_job(&ctx, &job.name, &job.metadata).await {
+        match run_with_lease(ctx, db, &job).await {
             Ok(_) => {
                 tracing::trace!("job successfully executed (id={})", job.id);
-                delete_job(&db, &job.id).await?;
+                finish_execution(db, execution_id, None).await?;
+                complete_job(db, &job).await?;
             }
             Err(e) => {
-                tracing::error!("job failed on execution (id={:?}, error={:?})", job.id, e);
-                update_job_error_message(&db, &job.id, &e.to_string()).await?;
+                let message = format!("{e:?}");
+                let retry_at = retry_policy(&job.name).next_attempt(job.attempts, Utc::now());
+                match retry_at {
+                    Some(retry_at) => tracing::error!(
+                        "job failed on execution, retrying at {retry_at} (id={:?}, error={:?})",
+                        job.id,
+                        e
+                    ),
+                    None => tracing::error!(
+                        "job failed on execution, giving up (id={:?}, error={:?})",
+                        job.id,
+                        e
+                    ),
+                }
+                finish_execution(db, execution_id, Some(&message)).await?;
+                fail_job(db, &job, &message, retry_at).await?;
             }
         }
     }
@@ -220,6 +248,32 @@ pub async fn run_scheduled_jobs(ctx: &Context, db: &DbClient) -> anyhow::Result<
     Ok(())
 }
 
+/// Runs a claimed job, renewing its lease until it finishes.
+///
+/// If the lease is lost, because it couldn't be renewed in time and another
+/// instance claimed the job, the job is aborted at its next `.await`. It may
+/// have done part of its work by then, so jobs are not guaranteed to run
+/// exactly once and should be safe to run again.
+async fn run_with_lease(ctx: &Context, db: &DbClient, job: &Job) -> anyhow::Result<()> {
+    let run = handle_job(ctx, &job.name, &job.metadata);
+    tokio::pin!(run);
+    let mut heartbeat = tokio::time::interval(std::time::Duration::from_secs(
+        LEASE_RENEWAL_INTERVAL_IN_SECS,
+    ));
+    // The first tick completes immediately, and the lease was just taken.
+    heartbeat.tick().await;
+    loop {
+        tokio::select! {
+            result = &mut run => return result,
+            _ = heartbeat.tick() => match renew_lease(db, job).await {
+                Ok(true) => {}
+                Ok(false) => anyhow::bail!("lost the lease of job {}", job.id),
+                Err(e) => tracing::warn!("failed to renew the lease of job {}: {e:?}", job.id),
+            },
+        }
+    }
+}
+
 static MIGRATIONS: &[&str] = &[
     "
 CREATE TABLE notifications (
@@ -273,4 +327,61 @@ CREATE UNIQUE INDEX jobs_name_scheduled_at_unique_index
         name, scheduled_at
     );
 ",
+    "
+CREATE TABLE github_events (
+    delivery_id TEXT PRIMARY KEY,
+    event TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    received_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    replayed_at TIMESTAMP WITH TIME ZONE,
+    error_message TEXT
+);
+",
+    "
+CREATE TABLE review_assignments (
+    repo TEXT NOT NULL,
+    issue_number INTEGER NOT NULL,
+    reviewer TEXT NOT NULL,
+    assigned_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    unassigned_at TIMESTAMP WITH TIME ZONE,
+    PRIMARY KEY (repo, issue_number, reviewer)
+);
+",
+    "CREATE INDEX review_assignments_reviewer_index ON review_assignments (reviewer);",
+    "
+CREATE TABLE review_prefs (
+    user_id BIGINT PRIMARY KEY,
+    username TEXT NOT NULL,
+    away_until DATE,
+    max_assigned_prs INTEGER
+);
+",
+    "CREATE INDEX review_prefs_username_index ON review_prefs (username);",
+    "ALTER TABLE jobs ADD COLUMN attempts INTEGER NOT NULL DEFAULT 0;",
+    "ALTER TABLE jobs ADD COLUMN retry_at TIMESTAMP WITH TIME ZONE;",
+    "ALTER TABLE jobs ADD COLUMN state TEXT NOT NULL DEFAULT 'pending';",
+    "
+CREATE TABLE job_executions (
+    id BIGSERIAL PRIMARY KEY,
+    job_id UUID NOT NULL,
+    name TEXT NOT NULL,
+    scheduled_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    attempt INTEGER NOT NULL,
+    started_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    finished_at TIMESTAMP WITH TIME ZONE,
+    error_message TEXT
+);
+",
+    "CREATE INDEX job_executions_started_at_index ON job_executions (started_at);",
+    // One-off jobs of the same kind can be scheduled at the same time for
+    // different issues.
+    "DROP INDEX jobs_name_scheduled_at_unique_index;",
+    "
+CREATE UNIQUE INDEX jobs_name_scheduled_at_metadata_unique_index
+    ON jobs (
+        name, scheduled_at, metadata
+    );
+",
+    "ALTER TABLE jobs ADD COLUMN lease_id UUID;",
+    "ALTER TABLE jobs ADD COLUMN lease_expires_at TIMESTAMP WITH TIME ZONE;",
 ];
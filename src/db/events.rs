@@ -0,0 +1,94 @@
+//! The `github_events` table keeps a copy of every webhook delivery accepted
+//! from GitHub, so that a delivery can be inspected and replayed after the
+//! fact (for example, after fixing a handler bug).
+use anyhow::{Context as _, Result};
+use chrono::{DateTime, Utc};
+use tokio_postgres::Client as DbClient;
+
+#[derive(Debug)]
+pub struct GithubEvent {
+    /// The value of the `X-GitHub-Delivery` header.
+    pub delivery_id: String,
+    /// The value of the `X-GitHub-Event` header.
+    pub event: String,
+    /// The raw JSON payload.
+    pub payload: String,
+    pub received_at: DateTime<Utc>,
+    /// When the delivery was last replayed, if ever.
+    pub replayed_at: Option<DateTime<Utc>>,
+    /// Errors from the handlers when the delivery was last processed.
+    pub error_message: Option<String>,
+}
+
+pub async fn record_event(
+    db: &DbClient,
+    delivery_id: &str,
+    event: &str,
+    payload: &str,
+) -> Result<()> {
+    tracing::trace!("record_event(delivery_id={}, event={})", delivery_id, event);
+
+    // GitHub reuses the delivery ID when a delivery is redelivered, in which
+    // case the stored payload is already correct.
+    db.execute(
+        "INSERT INTO github_events (delivery_id, event, payload) VALUES ($1, $2, $3)
+            ON CONFLICT (delivery_id) DO NOTHING",
+        &[&delivery_id, &event, &payload],
+    )
+    .await
+    .context("Inserting github event")?;
+
+    Ok(())
+}
+
+pub async fn update_event_error_message(
+    db: &DbClient,
+    delivery_id: &str,
+    message: Option<&str>,
+) -> Result<()> {
+    tracing::trace!("update_event_error_message(delivery_id={})", delivery_id);
+
+    db.execute(
+        "UPDATE github_events SET error_message = $2 WHERE delivery_id = $1",
+        &[&delivery_id, &message],
+    )
+    .await
+    .context("Updating github event error message")?;
+
+    Ok(())
+}
+
+pub async fn update_event_replayed_at(db: &DbClient, delivery_id: &str) -> Result<()> {
+    tracing::trace!("update_event_replayed_at(delivery_id={})", delivery_id);
+
+    db.execute(
+        "UPDATE github_events SET replayed_at = now() WHERE delivery_id = $1",
+        &[&delivery_id],
+    )
+    .await
+    .context("Updating github event replayed at")?;
+
+    Ok(())
+}
+
+pub async fn get_event(db: &DbClient, delivery_id: &str) -> Result<Option<GithubEvent>> {
+    tracing::trace!("get_event(delivery_id={})", delivery_id);
+
+    let row = db
+        .query_opt(
+            "SELECT delivery_id, event, payload, received_at, replayed_at, error_message
+                FROM github_events WHERE delivery_id = $1",
+            &[&delivery_id],
+        )
+        .await
+        .context("Select github event by delivery id")?;
+
+    Ok(row.map(|row| GithubEvent {
+        delivery_id: row.get(0),
+        event: row.get(1),
+        payload: row.get(2),
+        received_at: row.get(3),
+        replayed_at: row.get(4),
+        error_message: row.get(5),
+    }))
+}
@@ -1,30 +1,156 @@
 //! The `jobs` table provides a way to have scheduled jobs
+//!
+//! A job stays in the table until it succeeds, or until it has failed as many
+//! times as its [`RetryPolicy`] allows, after which it is kept in the `dead`
+//! state for inspection. Every attempt is recorded in the `job_executions`
+//! table.
 use anyhow::{Context as _, Result};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use cron::Schedule;
 use serde::{Deserialize, Serialize};
+use std::fmt;
 use tokio_postgres::Client as DbClient;
 use uuid::Uuid;
 
+/// How long executions are kept in the `job_executions` table.
+const EXECUTION_RETENTION_DAYS: i64 = 30;
+
+/// How long a claimed job is reserved for the instance running it. The
+/// lease is renewed every [`LEASE_RENEWAL_INTERVAL_IN_SECS`] while the job
+/// runs, so it only expires if the instance stops.
+const LEASE_DURATION_IN_SECS: f64 = 300.0;
+pub const LEASE_RENEWAL_INTERVAL_IN_SECS: u64 = 60;
+
 pub struct JobSchedule {
     pub name: String,
     pub schedule: Schedule,
     pub metadata: serde_json::Value,
 }
 
+/// How often and when a failed job is retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// The number of times the job is run before giving up, including the
+    /// first attempt.
+    pub max_attempts: i32,
+    /// How long to wait before the first retry. The delay doubles after
+    /// every attempt, up to `max_backoff`.
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// A policy for jobs that shouldn't be retried, for example because they
+    /// run often anyway.
+    pub fn never() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 1,
+            ..RetryPolicy::default()
+        }
+    }
+
+    /// Returns when to retry a job that has failed `attempts` times, or
+    /// `None` if it shouldn't be retried.
+    pub fn next_attempt(&self, attempts: i32, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        if attempts >= self.max_attempts {
+            return None;
+        }
+        let factor = 2i64.saturating_pow(attempts.saturating_sub(1).max(0) as u32);
+        let backoff = Duration::seconds(self.initial_backoff.num_seconds().saturating_mul(factor));
+        Some(now + backoff.min(self.max_backoff))
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 5,
+            initial_backoff: Duration::minutes(10),
+            max_backoff: Duration::hours(12),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    /// Waiting for `scheduled_at`, or for `retry_at` after a failure.
+    Pending,
+    Running,
+    /// Failed too many times, and won't be run again.
+    Dead,
+}
+
+impl JobState {
+    fn as_str(&self) -> &'static str {
+        match self {
+            JobState::Pending => "pending",
+            JobState::Running => "running",
+            JobState::Dead => "dead",
+        }
+    }
+
+    fn parse(s: &str) -> Result<JobState> {
+        Ok(match s {
+            "pending" => JobState::Pending,
+            "running" => JobState::Running,
+            "dead" => JobState::Dead,
+            _ => anyhow::bail!("unknown job state `{s}`"),
+        })
+    }
+}
+
+impl fmt::Display for JobState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Job {
     pub id: Uuid,
     pub name: String,
     pub scheduled_at: DateTime<Utc>,
     pub metadata: serde_json::Value,
+    /// When the job was last started.
     pub executed_at: Option<DateTime<Utc>>,
+    /// The error of the last attempt, if it failed.
     pub error_message: Option<String>,
+    /// The number of times the job was started.
+    pub attempts: i32,
+    /// When a failed job will be retried.
+    pub retry_at: Option<DateTime<Utc>>,
+    pub state: JobState,
+    /// Identifies the claim of the instance running the job.
+    pub lease_id: Option<Uuid>,
+    /// When another instance may claim the job if it's still running.
+    pub lease_expires_at: Option<DateTime<Utc>>,
+}
+
+/// An attempt to run a job, from the `job_executions` table.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct JobExecution {
+    pub id: i64,
+    pub job_id: Uuid,
+    pub name: String,
+    pub scheduled_at: DateTime<Utc>,
+    /// The attempt number, starting at 1.
+    pub attempt: i32,
+    pub started_at: DateTime<Utc>,
+    /// `None` while the job is running.
+    pub finished_at: Option<DateTime<Utc>>,
+    pub error_message: Option<String>,
+}
+
+impl JobExecution {
+    pub fn duration(&self) -> Option<Duration> {
+        self.finished_at.map(|finished| finished - self.started_at)
+    }
 }
 
 pub async fn insert_job(
     db: &DbClient,
-    name: &String,
+    name: &str,
     scheduled_at: &DateTime<Utc>,
     metadata: &serde_json::Value,
 ) -> Result<()> {
@@ -32,7 +158,7 @@ pub async fn insert_job(
 
     db.execute(
         "INSERT INTO jobs (name, scheduled_at, metadata) VALUES ($1, $2, $3) 
-            ON CONFLICT (name, scheduled_at) DO UPDATE SET metadata = EXCLUDED.metadata",
+            ON CONFLICT (name, scheduled_at, metadata) DO NOTHING",
         &[&name, &scheduled_at, &metadata],
     )
     .await
@@ -41,81 +167,148 @@ pub async fn insert_job(
     Ok(())
 }
 
-pub async fn delete_job(db: &DbClient, id: &Uuid) -> Result<()> {
-    tracing::trace!("delete_job(id={})", id);
+/// Claims the next job that is due, if any, marking it as running with a
+/// new lease.
+///
+/// Jobs are claimed in a single statement that skips the rows locked by
+/// other instances, so that each job is only claimed by one instance, even
+/// when several are running. A job whose lease has expired, because the
+/// instance running it stopped, can be claimed again, as can a job left
+/// running from before leases existed.
+pub async fn claim_job(db: &DbClient) -> Result<Option<Job>> {
+    let job = db
+        .query_opt(
+            "UPDATE jobs SET
+                executed_at = now(),
+                attempts = attempts + 1,
+                state = 'running',
+                lease_id = gen_random_uuid(),
+                lease_expires_at = now() + make_interval(secs => $1)
+            WHERE id = (
+                SELECT id FROM jobs
+                WHERE scheduled_at <= now() AND (
+                    (state = 'pending' AND (retry_at IS NULL OR retry_at <= now()))
+                    OR (state = 'running'
+                        AND (lease_expires_at IS NULL OR lease_expires_at <= now()))
+                )
+                ORDER BY scheduled_at
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING *",
+            &[&LEASE_DURATION_IN_SECS],
+        )
+        .await
+        .context("Claiming job")?;
+    job.as_ref().map(deserialize_job).transpose()
+}
+
+/// Extends the lease of a running job. Returns `false` if the job isn't
+/// held by this lease anymore.
+pub async fn renew_lease(db: &DbClient, job: &Job) -> Result<bool> {
+    tracing::trace!("renew_lease(id={})", job.id);
 
-    db.execute("DELETE FROM jobs WHERE id = $1", &[&id])
+    let updated = db
+        .execute(
+            "UPDATE jobs SET lease_expires_at = now() + make_interval(secs => $3)
+                WHERE id = $1 AND lease_id = $2",
+            &[&job.id, &job.lease_id, &LEASE_DURATION_IN_SECS],
+        )
         .await
-        .context("Deleting job")?;
+        .context("Renewing job lease")?;
+    Ok(updated == 1)
+}
 
-    Ok(())
+/// Records the start of an execution of a claimed job, whose id is returned.
+pub async fn start_execution(db: &DbClient, job: &Job) -> Result<i64> {
+    tracing::trace!("start_execution(id={})", job.id);
+
+    let row = db
+        .query_one(
+            "INSERT INTO job_executions (job_id, name, scheduled_at, attempt, started_at)
+                VALUES ($1, $2, $3, $4, $5)
+                RETURNING id",
+            &[
+                &job.id,
+                &job.name,
+                &job.scheduled_at,
+                &job.attempts,
+                &job.executed_at,
+            ],
+        )
+        .await
+        .context("Inserting job execution")?;
+    Ok(row.try_get(0)?)
 }
 
-pub async fn update_job_error_message(db: &DbClient, id: &Uuid, message: &String) -> Result<()> {
-    tracing::trace!("update_job_error_message(id={})", id);
+/// Deletes a job that succeeded, unless it isn't held by its lease anymore.
+pub async fn complete_job(db: &DbClient, job: &Job) -> Result<()> {
+    tracing::trace!("complete_job(id={})", job.id);
 
     db.execute(
-        "UPDATE jobs SET error_message = $2 WHERE id = $1",
-        &[&id, &message],
+        "DELETE FROM jobs WHERE id = $1 AND lease_id = $2",
+        &[&job.id, &job.lease_id],
     )
     .await
-    .context("Updating job error message")?;
+    .context("Deleting job")?;
 
     Ok(())
 }
 
-pub async fn update_job_executed_at(db: &DbClient, id: &Uuid) -> Result<()> {
-    tracing::trace!("update_job_executed_at(id={})", id);
+/// Records the end of an execution, with its error if it failed.
+pub async fn finish_execution(
+    db: &DbClient,
+    execution_id: i64,
+    error_message: Option<&str>,
+) -> Result<()> {
+    tracing::trace!("finish_execution(id={execution_id})");
 
-    db.execute("UPDATE jobs SET executed_at = now() WHERE id = $1", &[&id])
-        .await
-        .context("Updating job executed at")?;
+    db.execute(
+        "UPDATE job_executions SET finished_at = now(), error_message = $2 WHERE id = $1",
+        &[&execution_id, &error_message],
+    )
+    .await
+    .context("Updating job execution")?;
 
     Ok(())
 }
 
-pub async fn get_job_by_name_and_scheduled_at(
+/// Records the failure of a job, which is retried at `retry_at`, or marked as
+/// dead if that is `None`.
+pub async fn fail_job(
     db: &DbClient,
-    name: &String,
-    scheduled_at: &DateTime<Utc>,
-) -> Result<Job> {
-    tracing::trace!(
-        "get_job_by_name_and_scheduled_at(name={}, scheduled_at={})",
-        name,
-        scheduled_at
-    );
+    job: &Job,
+    message: &str,
+    retry_at: Option<DateTime<Utc>>,
+) -> Result<()> {
+    tracing::trace!("fail_job(id={}, retry_at={retry_at:?})", job.id);
 
-    let job = db
-        .query_one(
-            "SELECT * FROM jobs WHERE name = $1 AND scheduled_at = $2",
-            &[&name, &scheduled_at],
-        )
-        .await
-        .context("Select job by name and scheduled at")?;
+    let state = match retry_at {
+        Some(_) => JobState::Pending,
+        None => JobState::Dead,
+    };
+    db.execute(
+        "UPDATE jobs SET error_message = $3, retry_at = $4, state = $5, lease_id = NULL
+            WHERE id = $1 AND lease_id = $2",
+        &[&job.id, &job.lease_id, &message, &retry_at, &state.as_str()],
+    )
+    .await
+    .context("Updating job error message")?;
 
-    deserialize_job(&job)
+    Ok(())
 }
 
-// Selects all jobs with:
-//  - scheduled_at in the past
-//  - error_message is null or executed_at is at least 60 minutes ago (intended to make repeat executions rare enough)
-pub async fn get_jobs_to_execute(db: &DbClient) -> Result<Vec<Job>> {
-    let jobs = db
-        .query(
-            "
-        SELECT * FROM jobs WHERE scheduled_at <= now() AND (error_message IS NULL OR executed_at <= now() - INTERVAL '60 minutes')",
-            &[],
-        )
-        .await
-        .context("Getting jobs data")?;
-
-    let mut data = Vec::with_capacity(jobs.len());
-    for job in jobs {
-        let serialized_job = deserialize_job(&job);
-        data.push(serialized_job.unwrap());
-    }
+/// Deletes the executions that are older than the retention period.
+pub async fn delete_old_executions(db: &DbClient) -> Result<()> {
+    let before = Utc::now() - Duration::days(EXECUTION_RETENTION_DAYS);
+    db.execute(
+        "DELETE FROM job_executions WHERE started_at < $1",
+        &[&before],
+    )
+    .await
+    .context("Deleting old job executions")?;
 
-    Ok(data)
+    Ok(())
 }
 
 fn deserialize_job(row: &tokio_postgres::row::Row) -> Result<Job> {
@@ -125,6 +318,11 @@ fn deserialize_job(row: &tokio_postgres::row::Row) -> Result<Job> {
     let metadata: serde_json::Value = row.try_get(3)?;
     let executed_at: Option<DateTime<Utc>> = row.try_get(4)?;
     let error_message: Option<String> = row.try_get(5)?;
+    let attempts: i32 = row.try_get(6)?;
+    let retry_at: Option<DateTime<Utc>> = row.try_get(7)?;
+    let state: &str = row.try_get(8)?;
+    let lease_id: Option<Uuid> = row.try_get(9)?;
+    let lease_expires_at: Option<DateTime<Utc>> = row.try_get(10)?;
 
     Ok(Job {
         id,
@@ -133,5 +331,76 @@ fn deserialize_job(row: &tokio_postgres::row::Row) -> Result<Job> {
         metadata,
         executed_at,
         error_message,
+        attempts,
+        retry_at,
+        state: JobState::parse(state)?,
+        lease_id,
+        lease_expires_at,
     })
 }
+
+/// Returns all the jobs in the queue, including the dead ones, by date.
+pub async fn get_jobs(db: &DbClient) -> Result<Vec<Job>> {
+    let jobs = db
+        .query("SELECT * FROM jobs ORDER BY scheduled_at", &[])
+        .await
+        .context("Getting jobs")?;
+    jobs.iter().map(deserialize_job).collect()
+}
+
+/// Returns the most recent executions, the latest first.
+pub async fn get_recent_executions(db: &DbClient, limit: i64) -> Result<Vec<JobExecution>> {
+    let rows = db
+        .query(
+            "SELECT id, job_id, name, scheduled_at, attempt, started_at, finished_at, error_message
+                FROM job_executions
+                ORDER BY started_at DESC
+                LIMIT $1",
+            &[&limit],
+        )
+        .await
+        .context("Getting job executions")?;
+    rows.into_iter()
+        .map(|row| {
+            Ok(JobExecution {
+                id: row.try_get(0)?,
+                job_id: row.try_get(1)?,
+                name: row.try_get(2)?,
+                scheduled_at: row.try_get(3)?,
+                attempt: row.try_get(4)?,
+                started_at: row.try_get(5)?,
+                finished_at: row.try_get(6)?,
+                error_message: row.try_get(7)?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn retry_backoff() {
+        let policy = RetryPolicy {
+            max_attempts: 4,
+            initial_backoff: Duration::minutes(10),
+            max_backoff: Duration::minutes(30),
+        };
+        let now = Utc.ymd(2023, 1, 1).and_hms(0, 0, 0);
+        let delays: Vec<_> = (1..=4)
+            .map(|attempts| policy.next_attempt(attempts, now).map(|at| at - now))
+            .collect();
+        assert_eq!(
+            delays,
+            [
+                Some(Duration::minutes(10)),
+                Some(Duration::minutes(20)),
+                Some(Duration::minutes(30)),
+                None,
+            ]
+        );
+        assert_eq!(RetryPolicy::never().next_attempt(1, now), None);
+    }
+}
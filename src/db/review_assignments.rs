@@ -0,0 +1,185 @@
+//! The `review_assignments` table tracks which reviewers are assigned to which
+//! PRs, so that reviewers can be chosen based on their current workload.
+//!
+//! It is kept up to date from the `assigned`, `unassigned`, `closed` and
+//! `reopened` webhook events of repositories with an `[assign]` section.
+//! Reviewer names are stored in lowercase.
+use anyhow::{Context as _, Result};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use tokio_postgres::Client as DbClient;
+
+/// The workload of a reviewer, across all tracked repositories.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ReviewerLoad {
+    /// The number of open PRs the reviewer is assigned to.
+    pub open: i64,
+    /// The number of PRs the reviewer was assigned to since the requested
+    /// date, whether or not they are still open.
+    pub recent: i64,
+    /// When the reviewer was last assigned to a PR.
+    pub last_assigned_at: Option<DateTime<Utc>>,
+}
+
+pub async fn record_assignment(
+    db: &DbClient,
+    repo: &str,
+    issue_number: i32,
+    reviewer: &str,
+) -> Result<()> {
+    tracing::trace!(
+        "record_assignment(repo={repo}, issue_number={issue_number}, reviewer={reviewer})"
+    );
+
+    db.execute(
+        "INSERT INTO review_assignments (repo, issue_number, reviewer) VALUES ($1, $2, $3)
+            ON CONFLICT (repo, issue_number, reviewer)
+            DO UPDATE SET assigned_at = now(), unassigned_at = NULL",
+        &[&repo, &issue_number, &reviewer.to_lowercase()],
+    )
+    .await
+    .context("Inserting review assignment")?;
+
+    Ok(())
+}
+
+pub async fn record_unassignment(
+    db: &DbClient,
+    repo: &str,
+    issue_number: i32,
+    reviewer: &str,
+) -> Result<()> {
+    tracing::trace!(
+        "record_unassignment(repo={repo}, issue_number={issue_number}, reviewer={reviewer})"
+    );
+
+    db.execute(
+        "UPDATE review_assignments SET unassigned_at = now()
+            WHERE repo = $1 AND issue_number = $2 AND reviewer = $3 AND unassigned_at IS NULL",
+        &[&repo, &issue_number, &reviewer.to_lowercase()],
+    )
+    .await
+    .context("Updating review assignment")?;
+
+    Ok(())
+}
+
+/// Marks all assignments of a PR as finished, when it is closed or merged.
+pub async fn record_closed(db: &DbClient, repo: &str, issue_number: i32) -> Result<()> {
+    tracing::trace!("record_closed(repo={repo}, issue_number={issue_number})");
+
+    db.execute(
+        "UPDATE review_assignments SET unassigned_at = now()
+            WHERE repo = $1 AND issue_number = $2 AND unassigned_at IS NULL",
+        &[&repo, &issue_number],
+    )
+    .await
+    .context("Closing review assignments")?;
+
+    Ok(())
+}
+
+/// Marks the assignments of a reopened PR as active again, without counting
+/// them as new assignments.
+pub async fn record_reopened(
+    db: &DbClient,
+    repo: &str,
+    issue_number: i32,
+    reviewers: &[String],
+) -> Result<()> {
+    tracing::trace!("record_reopened(repo={repo}, issue_number={issue_number})");
+
+    let reviewers: Vec<String> = reviewers.iter().map(|r| r.to_lowercase()).collect();
+    db.execute(
+        "INSERT INTO review_assignments (repo, issue_number, reviewer)
+            SELECT $1, $2, reviewer FROM unnest($3::TEXT[]) AS reviewer
+            ON CONFLICT (repo, issue_number, reviewer)
+            DO UPDATE SET unassigned_at = NULL",
+        &[&repo, &issue_number, &reviewers],
+    )
+    .await
+    .context("Reopening review assignments")?;
+
+    Ok(())
+}
+
+#[derive(Debug)]
+pub struct OpenAssignment {
+    pub repo: String,
+    pub issue_number: i32,
+    pub reviewer: String,
+    pub assigned_at: DateTime<Utc>,
+}
+
+/// Returns the repositories where a reviewer was ever assigned, which are
+/// the repositories with an `[assign]` section.
+pub async fn repos(db: &DbClient) -> Result<Vec<String>> {
+    let rows = db
+        .query(
+            "SELECT DISTINCT repo FROM review_assignments ORDER BY repo",
+            &[],
+        )
+        .await
+        .context("Getting repositories with review assignments")?;
+
+    rows.into_iter().map(|row| Ok(row.try_get(0)?)).collect()
+}
+
+/// Returns the assignments of all open PRs, across all repositories.
+pub async fn open_assignments(db: &DbClient) -> Result<Vec<OpenAssignment>> {
+    let rows = db
+        .query(
+            "SELECT repo, issue_number, reviewer, assigned_at FROM review_assignments
+                WHERE unassigned_at IS NULL
+                ORDER BY repo, issue_number",
+            &[],
+        )
+        .await
+        .context("Getting open review assignments")?;
+
+    rows.into_iter()
+        .map(|row| {
+            Ok(OpenAssignment {
+                repo: row.try_get(0)?,
+                issue_number: row.try_get(1)?,
+                reviewer: row.try_get(2)?,
+                assigned_at: row.try_get(3)?,
+            })
+        })
+        .collect()
+}
+
+/// Returns the workload of the given reviewers, keyed by their lowercase
+/// name. Reviewers that were never assigned are not included.
+pub async fn reviewer_loads(
+    db: &DbClient,
+    reviewers: &[&str],
+    since: DateTime<Utc>,
+) -> Result<HashMap<String, ReviewerLoad>> {
+    let reviewers: Vec<String> = reviewers.iter().map(|r| r.to_lowercase()).collect();
+    let rows = db
+        .query(
+            "SELECT reviewer,
+                COUNT(*) FILTER (WHERE unassigned_at IS NULL),
+                COUNT(*) FILTER (WHERE assigned_at >= $2),
+                MAX(assigned_at)
+            FROM review_assignments
+            WHERE reviewer = ANY($1)
+            GROUP BY reviewer",
+            &[&reviewers, &since],
+        )
+        .await
+        .context("Getting reviewer loads")?;
+
+    let mut loads = HashMap::new();
+    for row in rows {
+        let reviewer: String = row.try_get(0)?;
+        let load = ReviewerLoad {
+            open: row.try_get(1)?,
+            recent: row.try_get(2)?,
+            last_assigned_at: row.try_get(3)?,
+        };
+        loads.insert(reviewer, load);
+    }
+    Ok(loads)
+}
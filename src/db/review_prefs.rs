@@ -0,0 +1,108 @@
+//! The `review_prefs` table stores the review availability that reviewers set
+//! for themselves through Zulip, such as being away until a given date or
+//! the maximum number of open PRs they want to be assigned to.
+//!
+//! Usernames are stored in lowercase, to match `review_assignments`.
+use anyhow::{Context as _, Result};
+use chrono::NaiveDate;
+use std::collections::HashMap;
+use tokio_postgres::Client as DbClient;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ReviewPrefs {
+    /// The reviewer shouldn't be assigned PRs before this date.
+    pub away_until: Option<NaiveDate>,
+    /// The maximum number of open PRs the reviewer wants to be assigned to.
+    pub max_assigned_prs: Option<i32>,
+}
+
+impl ReviewPrefs {
+    pub fn is_away(&self, today: NaiveDate) -> bool {
+        self.away_until.is_some_and(|until| today < until)
+    }
+}
+
+pub async fn set_away_until(
+    db: &DbClient,
+    user_id: i64,
+    username: &str,
+    away_until: Option<NaiveDate>,
+) -> Result<()> {
+    tracing::trace!("set_away_until(user_id={user_id}, away_until={away_until:?})");
+
+    db.execute(
+        "INSERT INTO review_prefs (user_id, username, away_until) VALUES ($1, $2, $3)
+            ON CONFLICT (user_id) DO UPDATE SET username = $2, away_until = $3",
+        &[&user_id, &username.to_lowercase(), &away_until],
+    )
+    .await
+    .context("Updating review away date")?;
+
+    Ok(())
+}
+
+pub async fn set_max_assigned_prs(
+    db: &DbClient,
+    user_id: i64,
+    username: &str,
+    max_assigned_prs: Option<i32>,
+) -> Result<()> {
+    tracing::trace!("set_max_assigned_prs(user_id={user_id}, max={max_assigned_prs:?})");
+
+    db.execute(
+        "INSERT INTO review_prefs (user_id, username, max_assigned_prs) VALUES ($1, $2, $3)
+            ON CONFLICT (user_id) DO UPDATE SET username = $2, max_assigned_prs = $3",
+        &[&user_id, &username.to_lowercase(), &max_assigned_prs],
+    )
+    .await
+    .context("Updating review capacity")?;
+
+    Ok(())
+}
+
+pub async fn get_prefs(db: &DbClient, user_id: i64) -> Result<ReviewPrefs> {
+    let row = db
+        .query_opt(
+            "SELECT away_until, max_assigned_prs FROM review_prefs WHERE user_id = $1",
+            &[&user_id],
+        )
+        .await
+        .context("Getting review preferences")?;
+    Ok(match row {
+        Some(row) => ReviewPrefs {
+            away_until: row.try_get(0)?,
+            max_assigned_prs: row.try_get(1)?,
+        },
+        None => ReviewPrefs::default(),
+    })
+}
+
+/// Returns the preferences of the given reviewers, keyed by their lowercase
+/// name. Reviewers without preferences are not included.
+pub async fn prefs_by_username(
+    db: &DbClient,
+    usernames: &[&str],
+) -> Result<HashMap<String, ReviewPrefs>> {
+    let usernames: Vec<String> = usernames.iter().map(|u| u.to_lowercase()).collect();
+    let rows = db
+        .query(
+            "SELECT username, away_until, max_assigned_prs FROM review_prefs
+                WHERE username = ANY($1)",
+            &[&usernames],
+        )
+        .await
+        .context("Getting review preferences")?;
+
+    let mut prefs = HashMap::new();
+    for row in rows {
+        let username: String = row.try_get(0)?;
+        prefs.insert(
+            username,
+            ReviewPrefs {
+                away_until: row.try_get(1)?,
+                max_assigned_prs: row.try_get(2)?,
+            },
+        );
+    }
+    Ok(prefs)
+}
@@ -0,0 +1,141 @@
+//! Dry-run mode, in which triagebot records the changes it would make to
+//! issues and pull requests instead of making them.
+//!
+//! Dry-run mode is enabled for every repository by setting the
+//! `TRIAGEBOT_DRY_RUN` environment variable, or for a single repository by
+//! adding an empty `[dry-run]` section to its `triagebot.toml`. This makes it
+//! possible to try out new configuration on a busy repository without the bot
+//! touching anything.
+//!
+//! The mutating methods of [`Issue`] check [`intercept`] before sending their
+//! request. Intercepted actions are logged, and the most recent ones are kept
+//! in memory to be shown on the `/dry-run` page.
+
+use crate::github::Issue;
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use tracing as log;
+
+/// The number of actions kept in memory.
+const MAX_ACTIONS: usize = 500;
+
+static GLOBAL_DRY_RUN: Lazy<bool> = Lazy::new(|| match std::env::var("TRIAGEBOT_DRY_RUN") {
+    Ok(value) => !matches!(value.as_str(), "" | "0" | "false"),
+    Err(_) => false,
+});
+
+static ACTIONS: Lazy<Mutex<VecDeque<DryRunAction>>> = Lazy::new(|| Mutex::new(VecDeque::new()));
+
+/// An action that was skipped because of dry-run mode.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DryRunAction {
+    pub time: DateTime<Utc>,
+    /// The full name of the repository, e.g. `rust-lang/rust`.
+    pub repo: String,
+    pub number: u64,
+    /// The name of the [`Issue`] method that was called, e.g. `add_labels`.
+    pub action: String,
+    /// A description of what the action would have done.
+    pub details: String,
+}
+
+/// Returns whether or not changes to the given repository are disabled.
+///
+/// The per-repository setting is read from the configuration cache, which is
+/// populated before any handler runs for an event in that repository.
+pub fn is_enabled(repo: &str) -> bool {
+    *GLOBAL_DRY_RUN || crate::config::cached_dry_run(repo)
+}
+
+/// Records `action` on `issue` and returns `true` if dry-run mode is enabled
+/// for its repository, in which case the caller should skip the action.
+pub(crate) fn intercept(issue: &Issue, action: &str, details: impl FnOnce() -> String) -> bool {
+    let repo = issue.repository().to_string();
+    if !is_enabled(&repo) {
+        return false;
+    }
+    let action = DryRunAction {
+        time: Utc::now(),
+        repo,
+        number: issue.number,
+        action: action.to_string(),
+        details: details(),
+    };
+    log::info!(
+        "dry run: {}#{} {}: {}",
+        action.repo,
+        action.number,
+        action.action,
+        action.details
+    );
+    let mut actions = ACTIONS.lock().unwrap();
+    if actions.len() == MAX_ACTIONS {
+        actions.pop_front();
+    }
+    actions.push_back(action);
+    true
+}
+
+/// Returns the recorded actions, most recent first, optionally only those for
+/// the given repository.
+pub fn actions(repo: Option<&str>) -> Vec<DryRunAction> {
+    ACTIONS
+        .lock()
+        .unwrap()
+        .iter()
+        .rev()
+        .filter(|action| repo.is_none() || repo == Some(action.repo.as_str()))
+        .cloned()
+        .collect()
+}
+
+/// Renders the `/dry-run` page.
+pub fn render(repo: Option<&str>) -> String {
+    let actions = actions(repo);
+
+    let mut out = String::new();
+    out.push_str("<html>");
+    out.push_str("<head>");
+    out.push_str("<meta charset=\"utf-8\">");
+    out.push_str("<title>Triagebot Dry Run</title>");
+    out.push_str("</head>");
+    out.push_str("<body>");
+
+    match repo {
+        Some(repo) => out.push_str(&format!("<h3>Dry-run actions for {}</h3>", escape(repo))),
+        None => out.push_str("<h3>Dry-run actions</h3>"),
+    }
+
+    if actions.is_empty() {
+        out.push_str("<p><em>No actions have been recorded.</em></p>");
+    } else {
+        out.push_str("<table>");
+        out.push_str("<tr><th>Time</th><th>Issue</th><th>Action</th><th>Details</th></tr>");
+        for action in actions {
+            out.push_str(&format!(
+                "<tr><td>{}</td><td><a href='https://github.com/{repo}/issues/{number}'>{repo}#{number}</a></td>\
+                 <td>{}</td><td><pre>{}</pre></td></tr>",
+                action.time.format("%Y-%m-%d %H:%M:%S UTC"),
+                escape(&action.action),
+                escape(&action.details),
+                repo = escape(&action.repo),
+                number = action.number,
+            ));
+        }
+        out.push_str("</table>");
+    }
+
+    out.push_str("</body>");
+    out.push_str("</html>");
+    out
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
@@ -1,3 +1,5 @@
+use crate::dry_run;
+use crate::test_record::{self, Service};
 use anyhow::{anyhow, Context};
 use async_trait::async_trait;
 use bytes::Bytes;
@@ -29,16 +31,27 @@ impl GithubClient {
         let req = req
             .build()
             .with_context(|| format!("building reqwest {}", req_dbg))?;
+        let record = test_record::is_recording().then(|| {
+            (
+                req.method().to_string(),
+                req.url().to_string(),
+                req.body().and_then(|b| b.as_bytes()).map(|b| b.to_vec()),
+            )
+        });
 
         let mut resp = self.client.execute(req.try_clone().unwrap()).await?;
         if let Some(sleep) = Self::needs_retry(&resp).await {
             resp = self.retry(req, sleep, MAX_ATTEMPTS).await?;
         }
+        let status = resp.status();
         let maybe_err = resp.error_for_status_ref().err();
         let body = resp
             .bytes()
             .await
             .with_context(|| format!("failed to read response body {req_dbg}"))?;
+        if let Some((method, url, request_body)) = record {
+            self.record_request(&method, &url, request_body.as_deref(), status, &body);
+        }
         if let Some(e) = maybe_err {
             return Err(anyhow::Error::new(e))
                 .with_context(|| format!("response: {}", String::from_utf8_lossy(&body)));
@@ -47,6 +60,39 @@ impl GithubClient {
         Ok((body, req_dbg))
     }
 
+    /// Records a request for [`test_record`], identifying the service from
+    /// the URL.
+    fn record_request(
+        &self,
+        method: &str,
+        url: &str,
+        request_body: Option<&[u8]>,
+        status: StatusCode,
+        response_body: &[u8],
+    ) {
+        // The GraphQL URL usually lives under the API URL, so check it first.
+        let services = [
+            (Service::Graphql, &self.graphql_url),
+            (Service::Raw, &self.raw_url),
+            (Service::TeamApi, &self.team_api_url),
+            (Service::Api, &self.api_url),
+        ];
+        for (service, base) in services {
+            if let Some(path) = url.strip_prefix(base.as_str()) {
+                test_record::record_request(
+                    service,
+                    method,
+                    path,
+                    request_body,
+                    status.as_u16(),
+                    response_body,
+                );
+                return;
+            }
+        }
+        log::warn!("not recording request to unknown service {url}");
+    }
+
     async fn needs_retry(resp: &Response) -> Option<Duration> {
         const REMAINING: &str = "X-RateLimit-Remaining";
         const RESET: &str = "X-RateLimit-Reset";
@@ -118,7 +164,7 @@ impl GithubClient {
                 .client
                 .execute(
                     self.client
-                        .get("https://api.github.com/rate_limit")
+                        .get(&format!("{}/rate_limit", self.api_url))
                         .configure(self)
                         .build()
                         .unwrap(),
@@ -171,7 +217,15 @@ impl GithubClient {
 
 impl User {
     pub async fn current(client: &GithubClient) -> anyhow::Result<Self> {
-        client.json(client.get("https://api.github.com/user")).await
+        client
+            .json(client.get(&format!("{}/user", client.api_url)))
+            .await
+    }
+
+    pub async fn by_id(client: &GithubClient, id: i64) -> anyhow::Result<Self> {
+        client
+            .json(client.get(&format!("{}/user/{id}", client.api_url)))
+            .await
     }
 
     pub async fn is_team_member<'a>(&'a self, client: &'a GithubClient) -> anyhow::Result<bool> {
@@ -246,7 +300,7 @@ pub struct Issue {
     pub number: u64,
     #[serde(deserialize_with = "opt_string")]
     pub body: String,
-    created_at: chrono::DateTime<Utc>,
+    pub created_at: chrono::DateTime<Utc>,
     pub updated_at: chrono::DateTime<Utc>,
     /// The SHA for a merge commit.
     ///
@@ -293,6 +347,11 @@ pub struct Issue {
     pub head: Option<CommitBase>,
     /// Whether it is open or closed.
     pub state: IssueState,
+    /// Users whose review of the PR is pending.
+    ///
+    /// Empty for issues, and for PRs in events that don't include it.
+    #[serde(default)]
+    pub requested_reviewers: Vec<User>,
 }
 
 #[derive(Debug, serde::Deserialize, Eq, PartialEq)]
@@ -338,14 +397,30 @@ pub struct Comment {
     pub pr_review_state: Option<PullRequestReviewState>,
 }
 
+/// The state of a review, which is lowercase in webhooks and uppercase in the
+/// REST API.
 #[derive(Debug, serde::Deserialize, Eq, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum PullRequestReviewState {
+    #[serde(alias = "APPROVED")]
     Approved,
+    #[serde(alias = "CHANGES_REQUESTED")]
     ChangesRequested,
+    #[serde(alias = "COMMENTED")]
     Commented,
+    #[serde(alias = "DISMISSED")]
     Dismissed,
+    #[serde(alias = "PENDING")]
+    Pending,
+}
+
+/// <https://docs.github.com/en/rest/commits/statuses>
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommitStatusState {
     Pending,
+    Success,
+    Failure,
 }
 
 fn opt_string<'de, D>(deserializer: D) -> Result<String, D::Error>
@@ -396,16 +471,20 @@ impl fmt::Display for IssueRepository {
 }
 
 impl IssueRepository {
-    fn url(&self) -> String {
+    fn url(&self, client: &GithubClient) -> String {
         format!(
-            "https://api.github.com/repos/{}/{}",
-            self.organization, self.repository
+            "{}/repos/{}/{}",
+            client.api_url, self.organization, self.repository
         )
     }
 
-    async fn has_label(&self, client: &GithubClient, label: &str) -> anyhow::Result<bool> {
+    pub(crate) async fn has_label(
+        &self,
+        client: &GithubClient,
+        label: &str,
+    ) -> anyhow::Result<bool> {
         #[allow(clippy::redundant_pattern_matching)]
-        let url = format!("{}/labels/{}", self.url(), label);
+        let url = format!("{}/labels/{}", self.url(client), label);
         match client.send_req(client.get(&url)).await {
             Ok(_) => Ok(true),
             Err(e) => {
@@ -475,13 +554,16 @@ impl Issue {
     }
 
     pub async fn get_comment(&self, client: &GithubClient, id: usize) -> anyhow::Result<Comment> {
-        let comment_url = format!("{}/issues/comments/{}", self.repository().url(), id);
+        let comment_url = format!("{}/issues/comments/{}", self.repository().url(client), id);
         let comment = client.json(client.get(&comment_url)).await?;
         Ok(comment)
     }
 
     pub async fn edit_body(&self, client: &GithubClient, body: &str) -> anyhow::Result<()> {
-        let edit_url = format!("{}/issues/{}", self.repository().url(), self.number);
+        if dry_run::intercept(self, "edit_body", || body.to_string()) {
+            return Ok(());
+        }
+        let edit_url = format!("{}/issues/{}", self.repository().url(client), self.number);
         #[derive(serde::Serialize)]
         struct ChangedIssue<'a> {
             body: &'a str,
@@ -499,7 +581,12 @@ impl Issue {
         id: usize,
         new_body: &str,
     ) -> anyhow::Result<()> {
-        let comment_url = format!("{}/issues/comments/{}", self.repository().url(), id);
+        if dry_run::intercept(self, "edit_comment", || {
+            format!("comment {id}:\n{new_body}")
+        }) {
+            return Ok(());
+        }
+        let comment_url = format!("{}/issues/comments/{}", self.repository().url(client), id);
         #[derive(serde::Serialize)]
         struct NewComment<'a> {
             body: &'a str,
@@ -516,12 +603,20 @@ impl Issue {
     }
 
     pub async fn post_comment(&self, client: &GithubClient, body: &str) -> anyhow::Result<()> {
+        if dry_run::intercept(self, "post_comment", || body.to_string()) {
+            return Ok(());
+        }
         #[derive(serde::Serialize)]
         struct PostComment<'a> {
             body: &'a str,
         }
+        let url = format!(
+            "{}/issues/{}/comments",
+            self.repository().url(client),
+            self.number
+        );
         client
-            .send_req(client.post(&self.comments_url).json(&PostComment { body }))
+            .send_req(client.post(&url).json(&PostComment { body }))
             .await
             .context("failed to post comment")?;
         Ok(())
@@ -532,7 +627,7 @@ impl Issue {
         // DELETE /repos/:owner/:repo/issues/:number/labels/{name}
         let url = format!(
             "{repo_url}/issues/{number}/labels/{name}",
-            repo_url = self.repository().url(),
+            repo_url = self.repository().url(client),
             number = self.number,
             name = label,
         );
@@ -546,6 +641,10 @@ impl Issue {
             return Ok(());
         }
 
+        if dry_run::intercept(self, "remove_label", || label.to_string()) {
+            return Ok(());
+        }
+
         client
             .send_req(client.delete(&url))
             .await
@@ -564,7 +663,7 @@ impl Issue {
         // repo_url = https://api.github.com/repos/Codertocat/Hello-World
         let url = format!(
             "{repo_url}/issues/{number}/labels",
-            repo_url = self.repository().url(),
+            repo_url = self.repository().url(client),
             number = self.number
         );
 
@@ -598,6 +697,10 @@ impl Issue {
             .into());
         }
 
+        if dry_run::intercept(self, "add_labels", || known_labels.join(", ")) {
+            return Ok(());
+        }
+
         #[derive(serde::Serialize)]
         struct LabelsReq {
             labels: Vec<String>,
@@ -629,9 +732,12 @@ impl Issue {
         selection: Selection<'_, str>,
     ) -> Result<(), AssignmentError> {
         log::info!("remove {:?} assignees for {}", selection, self.global_id());
+        if dry_run::intercept(self, "remove_assignees", || format!("{selection:?}")) {
+            return Ok(());
+        }
         let url = format!(
             "{repo_url}/issues/{number}/assignees",
-            repo_url = self.repository().url(),
+            repo_url = self.repository().url(client),
             number = self.number
         );
 
@@ -669,9 +775,12 @@ impl Issue {
         user: &str,
     ) -> Result<(), AssignmentError> {
         log::info!("add_assignee {} for {}", user, self.global_id());
+        if dry_run::intercept(self, "add_assignee", || user.to_string()) {
+            return Ok(());
+        }
         let url = format!(
             "{repo_url}/issues/{number}/assignees",
-            repo_url = self.repository().url(),
+            repo_url = self.repository().url(client),
             number = self.number
         );
 
@@ -704,6 +813,9 @@ impl Issue {
         user: &str,
     ) -> Result<(), AssignmentError> {
         log::info!("set_assignee for {} to {}", self.global_id(), user);
+        if dry_run::intercept(self, "set_assignee", || user.to_string()) {
+            return Ok(());
+        }
         self.add_assignee(client, user).await?;
         self.remove_assignees(client, Selection::Except(user))
             .await?;
@@ -716,8 +828,11 @@ impl Issue {
             self.number,
             title
         );
+        if dry_run::intercept(self, "set_milestone", || title.to_string()) {
+            return Ok(());
+        }
 
-        let create_url = format!("{}/milestones", self.repository().url());
+        let create_url = format!("{}/milestones", self.repository().url(client));
         let resp = client
             .send_req(
                 client
@@ -729,7 +844,7 @@ impl Issue {
         // fine, it just means the milestone was already created.
         log::trace!("Created milestone: {:?}", resp);
 
-        let list_url = format!("{}/milestones", self.repository().url());
+        let list_url = format!("{}/milestones", self.repository().url(client));
         let milestone_list: Vec<Milestone> = client.json(client.get(&list_url)).await?;
         let milestone_no = if let Some(milestone) = milestone_list.iter().find(|v| v.title == title)
         {
@@ -746,7 +861,7 @@ impl Issue {
         struct SetMilestone {
             milestone: u64,
         }
-        let url = format!("{}/issues/{}", self.repository().url(), self.number);
+        let url = format!("{}/issues/{}", self.repository().url(client), self.number);
         client
             .send_req(client.patch(&url).json(&SetMilestone {
                 milestone: milestone_no,
@@ -757,7 +872,10 @@ impl Issue {
     }
 
     pub async fn close(&self, client: &GithubClient) -> anyhow::Result<()> {
-        let edit_url = format!("{}/issues/{}", self.repository().url(), self.number);
+        if dry_run::intercept(self, "close", String::new) {
+            return Ok(());
+        }
+        let edit_url = format!("{}/issues/{}", self.repository().url(client), self.number);
         #[derive(serde::Serialize)]
         struct CloseIssue<'a> {
             state: &'a str,
@@ -783,7 +901,7 @@ impl Issue {
 
         let mut req = client.get(&format!(
             "{}/compare/{}...{}",
-            self.repository().url(),
+            self.repository().url(client),
             before,
             after
         ));
@@ -805,7 +923,7 @@ impl Issue {
         loop {
             let req = client.get(&format!(
                 "{}/pulls/{}/commits?page={page}&per_page=100",
-                self.repository().url(),
+                self.repository().url(client),
                 self.number
             ));
 
@@ -820,17 +938,256 @@ impl Issue {
         Ok(commits)
     }
 
-    pub async fn files(&self, client: &GithubClient) -> anyhow::Result<Vec<PullRequestFile>> {
+    /// Returns the comments on this issue or PR that were created or updated
+    /// after `since`.
+    pub async fn comments_since(
+        &self,
+        client: &GithubClient,
+        since: chrono::DateTime<Utc>,
+    ) -> anyhow::Result<Vec<Comment>> {
+        let since = since.to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+        let mut comments = Vec::new();
+        let mut page = 1;
+        loop {
+            let req = client.get(&format!(
+                "{}/issues/{}/comments?since={since}&page={page}&per_page=100",
+                self.repository().url(client),
+                self.number
+            ));
+
+            let new: Vec<_> = client.json(req).await?;
+            if new.is_empty() {
+                break;
+            }
+            comments.extend(new);
+
+            page += 1;
+        }
+        Ok(comments)
+    }
+
+    /// Returns when the user was last assigned to this issue or PR, or asked
+    /// to review it.
+    pub async fn assigned_at(
+        &self,
+        client: &GithubClient,
+        user: &str,
+    ) -> anyhow::Result<Option<chrono::DateTime<Utc>>> {
+        #[derive(serde::Deserialize)]
+        struct IssueEvent {
+            event: String,
+            assignee: Option<User>,
+            requested_reviewer: Option<User>,
+            created_at: chrono::DateTime<Utc>,
+        }
+
+        let mut assigned_at = None;
+        let mut page = 1;
+        loop {
+            let req = client.get(&format!(
+                "{}/issues/{}/events?page={page}&per_page=100",
+                self.repository().url(client),
+                self.number
+            ));
+
+            let new: Vec<IssueEvent> = client.json(req).await?;
+            if new.is_empty() {
+                break;
+            }
+            for event in new {
+                let target = match event.event.as_str() {
+                    "assigned" => event.assignee,
+                    "review_requested" => event.requested_reviewer,
+                    _ => None,
+                };
+                if target.is_some_and(|u| u.login.eq_ignore_ascii_case(user)) {
+                    assigned_at = assigned_at.max(Some(event.created_at));
+                }
+            }
+
+            page += 1;
+        }
+        Ok(assigned_at)
+    }
+
+    /// Returns the reviews submitted on this pull request.
+    pub async fn reviews(&self, client: &GithubClient) -> anyhow::Result<Vec<Comment>> {
         if !self.is_pr() {
             return Ok(vec![]);
         }
 
+        let mut reviews = Vec::new();
+        let mut page = 1;
+        loop {
+            let req = client.get(&format!(
+                "{}/pulls/{}/reviews?page={page}&per_page=100",
+                self.repository().url(client),
+                self.number
+            ));
+
+            let new: Vec<_> = client.json(req).await?;
+            if new.is_empty() {
+                break;
+            }
+            reviews.extend(new);
+
+            page += 1;
+        }
+        Ok(reviews)
+    }
+
+    /// Sets a commit status on the head commit of this pull request.
+    pub async fn set_head_status(
+        &self,
+        client: &GithubClient,
+        state: CommitStatusState,
+        context: &str,
+        description: &str,
+    ) -> anyhow::Result<()> {
+        let Some(head) = &self.head else {
+            anyhow::bail!("{} has no head commit", self.global_id());
+        };
+        log::info!(
+            "set_head_status {:?} for {} ({context}: {description})",
+            state,
+            self.global_id()
+        );
+        if dry_run::intercept(self, "set_head_status", || {
+            format!("{context}: {state:?}: {description}")
+        }) {
+            return Ok(());
+        }
+        let url = format!("{}/statuses/{}", self.repository().url(client), head.sha);
+        #[derive(serde::Serialize)]
+        struct StatusReq<'a> {
+            state: CommitStatusState,
+            context: &'a str,
+            description: &'a str,
+        }
+        client
+            .send_req(client.post(&url).json(&StatusReq {
+                state,
+                context,
+                description,
+            }))
+            .await
+            .context("failed to set commit status")?;
+        Ok(())
+    }
+
+    /// Requests a review of this pull request from the given users.
+    ///
+    /// Users who have already reviewed are asked to review again.
+    pub async fn request_reviewers(
+        &self,
+        client: &GithubClient,
+        reviewers: &[&str],
+    ) -> anyhow::Result<()> {
+        log::info!("request_reviewers {:?} for {}", reviewers, self.global_id());
+        if dry_run::intercept(self, "request_reviewers", || reviewers.join(", ")) {
+            return Ok(());
+        }
+        let url = format!(
+            "{}/pulls/{}/requested_reviewers",
+            self.repository().url(client),
+            self.number
+        );
+        #[derive(serde::Serialize)]
+        struct ReviewersReq<'a> {
+            reviewers: &'a [&'a str],
+        }
+        client
+            .send_req(client.post(&url).json(&ReviewersReq { reviewers }))
+            .await
+            .context("failed to request reviewers")?;
+        Ok(())
+    }
+
+    /// Removes pending review requests of this pull request.
+    pub async fn remove_requested_reviewers(
+        &self,
+        client: &GithubClient,
+        reviewers: &[&str],
+    ) -> anyhow::Result<()> {
+        log::info!(
+            "remove_requested_reviewers {:?} for {}",
+            reviewers,
+            self.global_id()
+        );
+        if dry_run::intercept(self, "remove_requested_reviewers", || reviewers.join(", ")) {
+            return Ok(());
+        }
+        let url = format!(
+            "{}/pulls/{}/requested_reviewers",
+            self.repository().url(client),
+            self.number
+        );
+        #[derive(serde::Serialize)]
+        struct ReviewersReq<'a> {
+            reviewers: &'a [&'a str],
+        }
+        client
+            .send_req(client.delete(&url).json(&ReviewersReq { reviewers }))
+            .await
+            .context("failed to remove requested reviewers")?;
+        Ok(())
+    }
+
+    /// Returns the users whose review of this pull request is pending.
+    ///
+    /// Unlike the `requested_reviewers` field, this is also available for
+    /// `issue_comment` events, whose payload doesn't include them.
+    pub async fn get_requested_reviewers(
+        &self,
+        client: &GithubClient,
+    ) -> anyhow::Result<Vec<User>> {
+        #[derive(serde::Deserialize)]
+        struct RequestedReviewers {
+            users: Vec<User>,
+        }
         let req = client.get(&format!(
-            "{}/pulls/{}/files",
-            self.repository().url(),
+            "{}/pulls/{}/requested_reviewers",
+            self.repository().url(client),
             self.number
         ));
-        Ok(client.json(req).await?)
+        let requested: RequestedReviewers = client.json(req).await?;
+        Ok(requested.users)
+    }
+
+    /// Returns whether a review of this pull request is pending from the user.
+    pub fn contain_requested_reviewer(&self, user: &str) -> bool {
+        self.requested_reviewers
+            .iter()
+            .any(|r| r.login.to_lowercase() == user.to_lowercase())
+    }
+
+    /// Returns the files modified by this pull request (no files are returned
+    /// if this `Issue` is not a pull request).
+    ///
+    /// GitHub returns at most 3000 files.
+    pub async fn files(&self, client: &GithubClient) -> anyhow::Result<Vec<PullRequestFile>> {
+        if !self.is_pr() {
+            return Ok(vec![]);
+        }
+
+        let mut files = Vec::new();
+        let mut page = 1;
+        loop {
+            let req = client.get(&format!(
+                "{}/pulls/{}/files?page={page}&per_page=100",
+                self.repository().url(client),
+                self.number
+            ));
+
+            let new: Vec<_> = client.json(req).await?;
+            if new.is_empty() {
+                break;
+            }
+            files.extend(new);
+
+            page += 1;
+        }
+        Ok(files)
     }
 }
 
@@ -839,6 +1196,100 @@ pub struct PullRequestFile {
     pub sha: String,
     pub filename: String,
     pub blob_url: String,
+    /// The diff of the file, which GitHub omits for binary and very large
+    /// files.
+    #[serde(default)]
+    pub patch: Option<String>,
+}
+
+/// The diff, files and commits of the pull request of an event, fetched the
+/// first time a handler asks for them.
+///
+/// One is shared by all the handlers of an event, so that GitHub is only
+/// queried once per event no matter how many handlers look at the diff.
+pub struct PullRequestData<'a> {
+    issue: &'a Issue,
+    diff: tokio::sync::OnceCell<Option<String>>,
+    files: tokio::sync::OnceCell<Vec<PullRequestFile>>,
+    commits: tokio::sync::OnceCell<Vec<GithubCommit>>,
+}
+
+impl<'a> PullRequestData<'a> {
+    pub fn new(issue: &'a Issue) -> Self {
+        PullRequestData {
+            issue,
+            diff: tokio::sync::OnceCell::new(),
+            files: tokio::sync::OnceCell::new(),
+            commits: tokio::sync::OnceCell::new(),
+        }
+    }
+
+    pub fn issue(&self) -> &'a Issue {
+        self.issue
+    }
+
+    /// Returns the diff of the pull request, see [`Issue::diff`].
+    ///
+    /// If the diff is too large for GitHub to generate, it is rebuilt from
+    /// the patches of [`Issue::files`] instead. That diff only has the
+    /// `diff --git` header and hunks of each file, which is enough for
+    /// [`files_changed`].
+    pub async fn diff(&self, client: &GithubClient) -> anyhow::Result<Option<&str>> {
+        let diff = self
+            .diff
+            .get_or_try_init(|| async {
+                match self.issue.diff(client).await {
+                    Err(e) if is_too_large(&e) => {
+                        log::info!(
+                            "diff of {} is too large, using the files API",
+                            self.issue.global_id()
+                        );
+                        Ok(Some(diff_from_files(self.files(client).await?)))
+                    }
+                    result => result,
+                }
+            })
+            .await?;
+        Ok(diff.as_deref())
+    }
+
+    /// Returns the files modified by the pull request, see [`Issue::files`].
+    pub async fn files(&self, client: &GithubClient) -> anyhow::Result<&[PullRequestFile]> {
+        let files = self
+            .files
+            .get_or_try_init(|| self.issue.files(client))
+            .await?;
+        Ok(files)
+    }
+
+    /// Returns the commits of the pull request, see [`Issue::commits`].
+    pub async fn commits(&self, client: &GithubClient) -> anyhow::Result<&[GithubCommit]> {
+        let commits = self
+            .commits
+            .get_or_try_init(|| self.issue.commits(client))
+            .await?;
+        Ok(commits)
+    }
+}
+
+/// Returns whether GitHub refused to generate a diff because it is too large.
+fn is_too_large(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<reqwest::Error>()
+        .and_then(|e| e.status())
+        .map_or(false, |status| status == StatusCode::NOT_ACCEPTABLE)
+}
+
+/// Builds a diff from the patches of the files API.
+fn diff_from_files(files: &[PullRequestFile]) -> String {
+    let mut diff = String::new();
+    for file in files {
+        diff.push_str(&format!("diff --git a/{0} b/{0}\n", file.filename));
+        if let Some(patch) = &file.patch {
+            diff.push_str(patch);
+            diff.push('\n');
+        }
+    }
+    diff
 }
 
 #[derive(serde::Serialize)]
@@ -947,6 +1398,14 @@ pub struct IssuesEvent {
     pub repository: Repository,
     /// Some if action is IssuesAction::Labeled, for example
     pub label: Option<Label>,
+    /// Some if action is IssuesAction::Assigned or IssuesAction::Unassigned.
+    pub assignee: Option<User>,
+    /// Some if action is IssuesAction::ReviewRequested or
+    /// IssuesAction::ReviewRequestRemoved, and a user (rather than a team)
+    /// was requested.
+    pub requested_reviewer: Option<User>,
+    /// The user that triggered the event.
+    pub sender: User,
 }
 
 #[derive(Debug, serde::Deserialize)]
@@ -954,7 +1413,7 @@ struct PullRequestEventFields {}
 
 #[derive(Clone, Debug, serde::Deserialize)]
 pub struct CommitBase {
-    sha: String,
+    pub sha: String,
     #[serde(rename = "ref")]
     pub git_ref: String,
     pub repo: Repository,
@@ -1000,11 +1459,8 @@ struct Ordering<'a> {
 }
 
 impl Repository {
-    const GITHUB_API_URL: &'static str = "https://api.github.com";
-    const GITHUB_GRAPHQL_API_URL: &'static str = "https://api.github.com/graphql";
-
-    fn url(&self) -> String {
-        format!("{}/repos/{}", Repository::GITHUB_API_URL, self.full_name)
+    fn url(&self, client: &GithubClient) -> String {
+        format!("{}/repos/{}", client.api_url, self.full_name)
     }
 
     pub fn owner(&self) -> &str {
@@ -1015,6 +1471,24 @@ impl Repository {
         self.full_name.split_once('/').unwrap().1
     }
 
+    pub async fn get_issue(&self, client: &GithubClient, number: u64) -> anyhow::Result<Issue> {
+        let url = format!("{}/issues/{number}", self.url(client));
+        client
+            .json(client.get(&url))
+            .await
+            .with_context(|| format!("{} failed to get issue {number}", self.full_name))
+    }
+
+    pub async fn get_pr(&self, client: &GithubClient, number: u64) -> anyhow::Result<Issue> {
+        let url = format!("{}/pulls/{number}", self.url(client));
+        let mut issue: Issue = client
+            .json(client.get(&url))
+            .await
+            .with_context(|| format!("{} failed to get pr {number}", self.full_name))?;
+        issue.pull_request = Some(PullRequestDetails {});
+        Ok(issue)
+    }
+
     pub async fn get_issues<'a>(
         &self,
         client: &GithubClient,
@@ -1066,11 +1540,17 @@ impl Repository {
         let mut issues = vec![];
         loop {
             let url = if use_search_api {
-                self.build_search_issues_url(&filters, include_labels, exclude_labels, ordering)
+                self.build_search_issues_url(
+                    client,
+                    &filters,
+                    include_labels,
+                    exclude_labels,
+                    ordering,
+                )
             } else if is_pr {
-                self.build_pulls_url(&filters, include_labels, ordering)
+                self.build_pulls_url(client, &filters, include_labels, ordering)
             } else {
-                self.build_issues_url(&filters, include_labels, ordering)
+                self.build_issues_url(client, &filters, include_labels, ordering)
             };
 
             let result = client.get(&url);
@@ -1099,24 +1579,27 @@ impl Repository {
 
     fn build_issues_url(
         &self,
+        client: &GithubClient,
         filters: &Vec<(&str, &str)>,
         include_labels: &Vec<&str>,
         ordering: Ordering<'_>,
     ) -> String {
-        self.build_endpoint_url("issues", filters, include_labels, ordering)
+        self.build_endpoint_url(client, "issues", filters, include_labels, ordering)
     }
 
     fn build_pulls_url(
         &self,
+        client: &GithubClient,
         filters: &Vec<(&str, &str)>,
         include_labels: &Vec<&str>,
         ordering: Ordering<'_>,
     ) -> String {
-        self.build_endpoint_url("pulls", filters, include_labels, ordering)
+        self.build_endpoint_url(client, "pulls", filters, include_labels, ordering)
     }
 
     fn build_endpoint_url(
         &self,
+        client: &GithubClient,
         endpoint: &str,
         filters: &Vec<(&str, &str)>,
         include_labels: &Vec<&str>,
@@ -1139,15 +1622,13 @@ impl Repository {
             .join("&");
         format!(
             "{}/repos/{}/{}?{}",
-            Repository::GITHUB_API_URL,
-            self.full_name,
-            endpoint,
-            filters
+            client.api_url, self.full_name, endpoint, filters
         )
     }
 
     fn build_search_issues_url(
         &self,
+        client: &GithubClient,
         filters: &Vec<(&str, &str)>,
         include_labels: &Vec<&str>,
         exclude_labels: &Vec<&str>,
@@ -1172,7 +1653,7 @@ impl Repository {
             .join("+");
         format!(
             "{}/search/issues?q={}&sort={}&order={}&per_page={}&page={}",
-            Repository::GITHUB_API_URL,
+            client.api_url,
             filters,
             ordering.sort,
             ordering.direction,
@@ -1183,7 +1664,7 @@ impl Repository {
 
     /// Retrieves a git commit for the given SHA.
     pub async fn git_commit(&self, client: &GithubClient, sha: &str) -> anyhow::Result<GitCommit> {
-        let url = format!("{}/git/commits/{sha}", self.url());
+        let url = format!("{}/git/commits/{sha}", self.url(client));
         client
             .json(client.get(&url))
             .await
@@ -1198,7 +1679,7 @@ impl Repository {
         parents: &[&str],
         tree: &str,
     ) -> anyhow::Result<GitCommit> {
-        let url = format!("{}/git/commits", self.url());
+        let url = format!("{}/git/commits", self.url(client));
         client
             .json(client.post(&url).json(&serde_json::json!({
                 "message": message,
@@ -1215,7 +1696,7 @@ impl Repository {
         client: &GithubClient,
         refname: &str,
     ) -> anyhow::Result<GitReference> {
-        let url = format!("{}/git/ref/{}", self.url(), refname);
+        let url = format!("{}/git/ref/{}", self.url(client), refname);
         client
             .json(client.get(&url))
             .await
@@ -1229,7 +1710,7 @@ impl Repository {
         refname: &str,
         sha: &str,
     ) -> anyhow::Result<GitReference> {
-        let url = format!("{}/git/refs/{}", self.url(), refname);
+        let url = format!("{}/git/refs/{}", self.url(client), refname);
         client
             .json(client.patch(&url).json(&serde_json::json!({
                 "sha": sha,
@@ -1280,7 +1761,7 @@ impl Repository {
             let query = RecentCommits::build(args.clone());
             let data = client
                 .json::<cynic::GraphQlResponse<RecentCommits>>(
-                    client.post(Repository::GITHUB_GRAPHQL_API_URL).json(&query),
+                    client.post(&client.graphql_url).json(&query),
                 )
                 .await
                 .with_context(|| {
@@ -1419,7 +1900,7 @@ impl Repository {
         base_tree: &str,
         tree: &[GitTreeEntry],
     ) -> anyhow::Result<GitTreeObject> {
-        let url = format!("{}/git/trees", self.url());
+        let url = format!("{}/git/trees", self.url(client));
         client
             .json(client.post(&url).json(&serde_json::json!({
                 "base_tree": base_tree,
@@ -1444,7 +1925,7 @@ impl Repository {
         path: &str,
         refname: Option<&str>,
     ) -> anyhow::Result<Submodule> {
-        let mut url = format!("{}/contents/{}", self.url(), path);
+        let mut url = format!("{}/contents/{}", self.url(client), path);
         if let Some(refname) = refname {
             url.push_str("?ref=");
             url.push_str(refname);
@@ -1466,7 +1947,7 @@ impl Repository {
         base: &str,
         body: &str,
     ) -> anyhow::Result<Issue> {
-        let url = format!("{}/pulls", self.url());
+        let url = format!("{}/pulls", self.url(client));
         let mut issue: Issue = client
             .json(client.post(&url).json(&serde_json::json!({
                 "title": title,
@@ -1489,7 +1970,7 @@ impl Repository {
     ///
     /// **Warning**: This will to a force update if there are conflicts.
     pub async fn merge_upstream(&self, client: &GithubClient, branch: &str) -> anyhow::Result<()> {
-        let url = format!("{}/merge-upstream", self.url());
+        let url = format!("{}/merge-upstream", self.url(client));
         let merge_error = match client
             .send_req(client.post(&url).json(&serde_json::json!({
                 "branch": branch,
@@ -1670,10 +2151,32 @@ pub struct CreateEvent {
 pub struct PushEvent {
     #[serde(rename = "ref")]
     pub git_ref: String,
+    /// The SHA of the most recent commit on `ref` after the push.
+    pub after: String,
+    /// The pushed commits, limited to the 20 most recent.
+    #[serde(default)]
+    pub commits: Vec<PushCommit>,
     repository: Repository,
     sender: User,
 }
 
+impl PushEvent {
+    pub fn repository(&self) -> &Repository {
+        &self.repository
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct PushCommit {
+    pub id: String,
+    #[serde(default)]
+    pub added: Vec<String>,
+    #[serde(default)]
+    pub removed: Vec<String>,
+    #[serde(default)]
+    pub modified: Vec<String>,
+}
+
 /// An event triggered by a webhook.
 #[derive(Debug)]
 pub enum Event {
@@ -1808,11 +2311,51 @@ fn get_token_from_git_config() -> anyhow::Result<String> {
 pub struct GithubClient {
     token: String,
     client: Client,
+    api_url: String,
+    graphql_url: String,
+    raw_url: String,
+    team_api_url: String,
 }
 
 impl GithubClient {
+    /// Creates a client for the GitHub services.
+    ///
+    /// The URLs default to the real services, but can be overridden with the
+    /// `GITHUB_API_URL`, `GITHUB_GRAPHQL_API_URL`, `GITHUB_RAW_URL` and
+    /// `TEAM_API_URL` environment variables (for example, to point at a
+    /// local server during testing).
     pub fn new(client: Client, token: String) -> Self {
-        GithubClient { client, token }
+        fn url_from_env(var: &str, default: &str) -> String {
+            std::env::var(var).unwrap_or_else(|_| default.to_string())
+        }
+        GithubClient {
+            client,
+            token,
+            api_url: url_from_env("GITHUB_API_URL", "https://api.github.com"),
+            graphql_url: url_from_env("GITHUB_GRAPHQL_API_URL", "https://api.github.com/graphql"),
+            raw_url: url_from_env("GITHUB_RAW_URL", "https://raw.githubusercontent.com"),
+            team_api_url: url_from_env("TEAM_API_URL", rust_team_data::v1::BASE_URL),
+        }
+    }
+
+    /// Replaces the URLs of the GitHub REST API, GraphQL API, raw file
+    /// server, and team API that this client sends requests to.
+    pub fn with_urls(
+        mut self,
+        api_url: &str,
+        graphql_url: &str,
+        raw_url: &str,
+        team_api_url: &str,
+    ) -> Self {
+        self.api_url = api_url.to_string();
+        self.graphql_url = graphql_url.to_string();
+        self.raw_url = raw_url.to_string();
+        self.team_api_url = team_api_url.to_string();
+        self
+    }
+
+    pub(crate) fn team_api_url(&self) -> &str {
+        &self.team_api_url
     }
 
     pub fn new_with_default_token(client: Client) -> Self {
@@ -1829,10 +2372,7 @@ impl GithubClient {
         branch: &str,
         path: &str,
     ) -> anyhow::Result<Option<Bytes>> {
-        let url = format!(
-            "https://raw.githubusercontent.com/{}/{}/{}",
-            repo, branch, path
-        );
+        let url = format!("{}/{}/{}/{}", self.raw_url, repo, branch, path);
         let req = self.get(&url);
         let req_dbg = format!("{:?}", req);
         let req = req
@@ -1844,6 +2384,9 @@ impl GithubClient {
             .bytes()
             .await
             .with_context(|| format!("failed to read response body {req_dbg}"))?;
+        if test_record::is_recording() {
+            self.record_request("GET", &url, None, status, &body);
+        }
         match status {
             StatusCode::OK => Ok(Some(body)),
             StatusCode::NOT_FOUND => Ok(None),
@@ -1894,8 +2437,8 @@ impl GithubClient {
 
     pub async fn rust_commit(&self, sha: &str) -> Option<GithubCommit> {
         let req = self.get(&format!(
-            "https://api.github.com/repos/rust-lang/rust/commits/{}",
-            sha
+            "{}/repos/rust-lang/rust/commits/{}",
+            self.api_url, sha
         ));
         match self.json(req).await {
             Ok(r) => Some(r),
@@ -1908,7 +2451,10 @@ impl GithubClient {
 
     /// This does not retrieve all of them, only the last several.
     pub async fn bors_commits(&self) -> Vec<GithubCommit> {
-        let req = self.get("https://api.github.com/repos/rust-lang/rust/commits?author=bors");
+        let req = self.get(&format!(
+            "{}/repos/rust-lang/rust/commits?author=bors",
+            self.api_url
+        ));
         match self.json(req).await {
             Ok(r) => r,
             Err(e) => {
@@ -1924,13 +2470,10 @@ impl GithubClient {
         query: &str,
         vars: serde_json::Value,
     ) -> anyhow::Result<T> {
-        self.json(
-            self.post(Repository::GITHUB_GRAPHQL_API_URL)
-                .json(&serde_json::json!({
-                    "query": query,
-                    "variables": vars,
-                })),
-        )
+        self.json(self.post(&self.graphql_url).json(&serde_json::json!({
+            "query": query,
+            "variables": vars,
+        })))
         .await
     }
 
@@ -2041,7 +2584,7 @@ impl GithubClient {
     ///
     /// The `full_name` should be something like `rust-lang/rust`.
     pub async fn repository(&self, full_name: &str) -> anyhow::Result<Repository> {
-        let req = self.get(&format!("{}/repos/{full_name}", Repository::GITHUB_API_URL));
+        let req = self.get(&format!("{}/repos/{full_name}", self.api_url));
         self.json(req)
             .await
             .with_context(|| format!("{} failed to get repo", full_name))
@@ -2140,7 +2683,7 @@ impl IssuesQuery for LeastRecentlyReviewedPullRequests {
         };
         loop {
             let query = queries::LeastRecentlyReviewedPullRequests::build(args.clone());
-            let req = client.post(Repository::GITHUB_GRAPHQL_API_URL);
+            let req = client.post(&client.graphql_url);
             let req = req.json(&query);
 
             let data: cynic::GraphQlResponse<queries::LeastRecentlyReviewedPullRequests> =
@@ -2274,7 +2817,7 @@ async fn project_items_by_status(
     let mut all_items = vec![];
     loop {
         let query = project_items::Query::build(args.clone());
-        let req = client.post(Repository::GITHUB_GRAPHQL_API_URL);
+        let req = client.post(&client.graphql_url);
         let req = req.json(&query);
 
         let data: cynic::GraphQlResponse<project_items::Query> = client.json(req).await?;
@@ -2445,6 +2988,27 @@ index fb9cee43b2d..b484c25ea51 100644
         assert_eq!(files_changed(input), vec!["triagebot.toml".to_string()]);
     }
 
+    #[test]
+    fn diff_from_files_api() {
+        let files: Vec<PullRequestFile> = serde_json::from_value(serde_json::json!([
+            {
+                "sha": "1",
+                "filename": "src/lib.rs",
+                "blob_url": "",
+                "patch": "@@ -1 +1 @@\n-a\n+b",
+            },
+            {
+                "sha": "2",
+                "filename": "logo.png",
+                "blob_url": "",
+            },
+        ]))
+        .unwrap();
+        let diff = diff_from_files(&files);
+        assert_eq!(files_changed(&diff), vec!["src/lib.rs", "logo.png"]);
+        assert!(diff.contains("\n+b\n"));
+    }
+
     #[test]
     fn extract_several_files() {
         let input = r##"\
@@ -1,5 +1,7 @@
-use crate::config::{self, Config, ConfigurationError};
-use crate::github::{Event, GithubClient, IssueCommentAction, IssuesAction, IssuesEvent};
+use crate::config::{self, Config, ConfigurationError, ShortcutConfig};
+use crate::github::{
+    Event, GithubClient, IssueCommentAction, IssuesAction, IssuesEvent, PullRequestData,
+};
 use octocrab::Octocrab;
 use parser::command::{assign::AssignCommand, Command, Input};
 use std::fmt;
@@ -41,27 +43,37 @@ mod notify_zulip;
 mod ping;
 mod prioritize;
 mod relabel;
+mod required_reviews;
 mod review_submitted;
 mod rfc_helper;
 pub mod rustc_commits;
 mod shortcut;
+pub mod stale_reviews;
+pub mod validate_config;
 
 pub async fn handle(ctx: &Context, event: &Event) -> Vec<HandlerError> {
+    if let Event::Push(push) = event {
+        config::handle_push(&ctx.github, push).await;
+    }
     let config = config::get(&ctx.github, event.repo()).await;
     if let Err(e) = &config {
         log::warn!("configuration error {}: {e}", event.repo().full_name);
     }
     let mut errors = Vec::new();
+    // Shared by the handlers, so that the diff, files and commits of a PR are
+    // fetched at most once per event.
+    let pr = event.issue().map(PullRequestData::new);
 
-    if let (Ok(config), Event::Issue(event)) = (config.as_ref(), event) {
-        handle_issue(ctx, event, config, &mut errors).await;
+    if let (Ok(config), Event::Issue(event), Some(pr)) = (config.as_ref(), event, &pr) {
+        handle_issue(ctx, event, pr, config, &mut errors).await;
     }
 
     if let Some(body) = event.comment_body() {
         handle_command(ctx, event, &config, body, &mut errors).await;
     }
 
-    if let Err(e) = notification::handle(ctx, event).await {
+    let notification_config = config.as_ref().ok().and_then(|c| c.notification.as_ref());
+    if let Err(e) = notification::handle(ctx, event, notification_config).await {
         log::error!(
             "failed to process event {:?} with notification handler: {:?}",
             event,
@@ -85,12 +97,36 @@ pub async fn handle(ctx: &Context, event: &Event) -> Vec<HandlerError> {
         );
     }
 
-    if let Err(e) = rfc_helper::handle(ctx, event).await {
-        log::error!(
-            "failed to process event {:?} with rfc_helper handler: {:?}",
-            event,
-            e
-        );
+    if let Some(pr) = &pr {
+        if let Err(e) = rfc_helper::handle(ctx, event, pr).await {
+            log::error!(
+                "failed to process event {:?} with rfc_helper handler: {:?}",
+                event,
+                e
+            );
+        }
+    }
+
+    if let (Event::Issue(event), Some(pr)) = (event, &pr) {
+        if let Err(e) = validate_config::handle(ctx, event, pr).await {
+            log::error!(
+                "failed to process event {:?} with validate_config handler: {:?}",
+                event,
+                e
+            );
+        }
+    }
+
+    if let (Some(assign_config), Event::Issue(event)) =
+        (config.as_ref().ok().and_then(|c| c.assign.as_ref()), event)
+    {
+        if let Err(e) = assign::track_assignments(ctx, assign_config, event).await {
+            log::error!(
+                "failed to process event {:?} with assignment tracking: {:?}",
+                event,
+                e
+            );
+        }
     }
 
     if let Some(config) = config
@@ -107,6 +143,26 @@ pub async fn handle(ctx: &Context, event: &Event) -> Vec<HandlerError> {
         }
     }
 
+    if let (Ok(config), Some(pr)) = (&config, &pr) {
+        if let Some(required_reviews_config) = &config.required_reviews {
+            if let Err(e) = required_reviews::handle(
+                ctx,
+                event,
+                pr,
+                required_reviews_config,
+                config.assign.as_ref(),
+            )
+            .await
+            {
+                log::error!(
+                    "failed to process event {:?} with required_reviews handler: {:?}",
+                    event,
+                    e
+                );
+            }
+        }
+    }
+
     if let Some(ghr_config) = config
         .as_ref()
         .ok()
@@ -129,11 +185,12 @@ macro_rules! issue_handlers {
         async fn handle_issue(
             ctx: &Context,
             event: &IssuesEvent,
+            pr: &PullRequestData<'_>,
             config: &Arc<Config>,
             errors: &mut Vec<HandlerError>,
         ) {
             $(
-            match $name::parse_input(ctx, event, config.$name.as_ref()).await {
+            match $name::parse_input(ctx, event, pr, config.$name.as_ref()).await {
                 Err(err) => errors.push(HandlerError::Message(err)),
                 Ok(Some(input)) => {
                     if let Some(config) = &config.$name {
@@ -157,6 +214,7 @@ macro_rules! issue_handlers {
 //
 // This is for events that happen only on issues (e.g. label changes).
 // Each module in the list must contain the functions `parse_input` and `handle_input`.
+// `parse_input` is given the data of the PR shared by all the handlers.
 issue_handlers! {
     assign,
     autolabel,
@@ -192,9 +250,15 @@ macro_rules! command_handlers {
                 }
             }
 
-            let input = Input::new(&body, vec![&ctx.username, "triagebot"]);
+            let shortcuts = match config.as_ref().ok().and_then(|c| c.shortcut.as_ref()) {
+                Some(shortcut) => shortcut.names(),
+                // Still recognize the default shortcuts, to report that the
+                // feature isn't enabled.
+                None => ShortcutConfig::default_names(),
+            };
+            let input = Input::new(&body, vec![&ctx.username, "triagebot"]).with_shortcuts(shortcuts.clone());
             let commands = if let Some(previous) = event.comment_from() {
-                let prev_commands = Input::new(&previous, vec![&ctx.username, "triagebot"]).collect::<Vec<_>>();
+                let prev_commands = Input::new(&previous, vec![&ctx.username, "triagebot"]).with_shortcuts(shortcuts).collect::<Vec<_>>();
                 input.filter(|cmd| !prev_commands.contains(cmd)).collect::<Vec<_>>()
             } else {
                 input.collect()
@@ -214,13 +278,13 @@ macro_rules! command_handlers {
                     // case, just ignore it.
                     if commands
                         .iter()
-                        .all(|cmd| matches!(cmd, Command::Assign(Ok(AssignCommand::ReviewName { .. }))))
+                        .all(|cmd| matches!(cmd, Command::Assign(Ok(AssignCommand::ReviewName { .. } | AssignCommand::ReviewExplain))))
                     {
                         return;
                     }
                     return errors.push(HandlerError::Message(e.to_string()));
                 }
-                Err(e @ ConfigurationError::Toml(_)) => {
+                Err(e @ (ConfigurationError::Toml(_) | ConfigurationError::OrgToml(..))) => {
                     return errors.push(HandlerError::Message(e.to_string()));
                 }
                 Err(e @ ConfigurationError::Http(_)) => {
@@ -272,6 +336,7 @@ command_handlers! {
     prioritize: Prioritize,
     relabel: Relabel,
     major_change: Second,
+    major_change: Concern,
     shortcut: Shortcut,
     close: Close,
     note: Note,
@@ -6,6 +6,10 @@
 //! * `@rustbot claim`: Assigns to the comment author.
 //! * `@rustbot release-assignment`: Removes the commenter's assignment.
 //! * `r? @user`: Assigns to the given user (PRs only).
+//! * `r? @user1 @user2`: Assigns to several users, for PRs needing more than
+//!   one sign-off.
+//! * `r? explain`: Posts how a reviewer would be chosen for the PR, without
+//!   assigning anyone.
 //!
 //! This is capable of assigning to any user, even if they do not have write
 //! access to the repo. It does this by fake-assigning the bot and adding a
@@ -15,27 +19,39 @@
 //!
 //! This also supports auto-assignment of new PRs. Based on rules in the
 //! `assign.owners` config, it will auto-select an assignee based on the files
-//! the PR modifies.
+//! the PR modifies. An owners entry can ask for several distinct reviewers
+//! with `min_reviewers`. With `assign.codeowners`, the repository's
+//! CODEOWNERS file is used for files that don't match any `owners` entry.
+//! The `assign.selection` config controls how the assignee is picked among
+//! the candidates, using the assignments tracked in the `review_assignments`
+//! table.
 
 use crate::{
-    config::AssignConfig,
-    github::{self, Event, Issue, IssuesAction, Selection},
+    config::{AssignConfig, ReviewMode, ReviewerSelection},
+    db::review_assignments::{self, ReviewerLoad},
+    db::review_prefs::{self, ReviewPrefs},
+    github::{self, Event, Issue, IssuesAction, PullRequestData, Selection},
     handlers::{Context, GithubClient, IssuesEvent},
     interactions::EditIssueBody,
 };
 use anyhow::{bail, Context as _};
+use codeowners::CodeOwners;
 use parser::command::assign::AssignCommand;
 use parser::command::{Command, Input};
-use rand::seq::IteratorRandom;
+use rand::seq::SliceRandom;
 use rust_team_data::v1::Teams;
 use std::collections::{HashMap, HashSet};
 use std::fmt;
 use tracing as log;
 
+pub(super) mod codeowners;
+
 #[cfg(test)]
 mod tests {
     mod tests_candidates;
+    mod tests_codeowners;
     mod tests_from_diff;
+    mod tests_selection;
 }
 
 const NEW_USER_WELCOME_MESSAGE: &str = "Thanks for the pull request, and welcome! \
@@ -50,11 +66,11 @@ when appropriate:
 - `@rustbot author`: the review is finished, PR author should check the comments and take action accordingly
 - `@rustbot review`: the author is ready for a review, this PR will be queued again in the reviewer's queue";
 
-const WELCOME_WITH_REVIEWER: &str = "@{assignee} (or someone else)";
+const WELCOME_WITH_REVIEWER: &str = "{assignee} (or someone else)";
 
 const WELCOME_WITHOUT_REVIEWER: &str = "@Mark-Simulacrum (NB. this repo may be misconfigured)";
 
-const RETURNING_USER_WELCOME_MESSAGE: &str = "r? @{assignee}
+const RETURNING_USER_WELCOME_MESSAGE: &str = "r? {assignee}
 
 (rustbot has picked a reviewer for you, use r? to override)";
 
@@ -70,15 +86,113 @@ const NON_DEFAULT_BRANCH: &str =
 
 const SUBMODULE_WARNING_MSG: &str = "These commits modify **submodules**.";
 
+/// How far back assignments count towards a reviewer's recent assignments
+/// with the `least-loaded` selection.
+const RECENT_ASSIGNMENT_DAYS: i64 = 30;
+
 fn on_vacation_msg(user: &str) -> String {
     ON_VACATION_WARNING.replace("{username}", user)
 }
 
+/// Mentions each of the users, like `@a @b`.
+fn mentions(users: &[String]) -> String {
+    users
+        .iter()
+        .map(|user| format!("@{user}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 #[derive(Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 struct AssignData {
     user: Option<String>,
 }
 
+/// How many of the files matching the same owners are listed by `r? explain`,
+/// so that the comment stays short on large PRs.
+const MAX_EXPLAINED_FILES: usize = 5;
+
+/// GitHub rejects comments longer than this.
+const MAX_COMMENT_LEN: usize = 65536;
+
+/// Collects the reasoning behind a reviewer selection for `r? explain`.
+///
+/// When disabled, nothing is recorded.
+pub(super) struct Explanation(Option<Vec<String>>);
+
+impl Explanation {
+    pub(super) fn disabled() -> Explanation {
+        Explanation(None)
+    }
+
+    fn enabled() -> Explanation {
+        Explanation(Some(Vec::new()))
+    }
+
+    fn note(&mut self, line: impl FnOnce() -> String) {
+        if let Some(lines) = &mut self.0 {
+            lines.push(line());
+        }
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Notes the owners entries matched by each modified file, or `None` for
+    /// files without owners. Files matching the same entries are grouped, and
+    /// only the first few of each group are listed.
+    fn note_files(&mut self, files: &[(&str, Option<String>)]) {
+        let Some(lines) = &mut self.0 else {
+            return;
+        };
+        let mut groups: Vec<(&Option<String>, Vec<&str>)> = Vec::new();
+        for (path, entries) in files {
+            match groups.iter_mut().find(|(e, _)| *e == entries) {
+                Some((_, paths)) => paths.push(path),
+                None => groups.push((entries, vec![path])),
+            }
+        }
+        for (entries, paths) in groups {
+            for path in paths.iter().take(MAX_EXPLAINED_FILES) {
+                lines.push(match entries {
+                    Some(entries) => format!("`{path}` matches {entries}"),
+                    None => format!("`{path}` doesn't match any owners"),
+                });
+            }
+            let more = paths.len().saturating_sub(MAX_EXPLAINED_FILES);
+            if more > 0 {
+                lines.push(match entries {
+                    Some(entries) => format!("{more} more file(s) match {entries}"),
+                    None => format!("{more} more file(s) don't match any owners"),
+                });
+            }
+        }
+    }
+
+    fn lines(&self) -> &[String] {
+        self.0.as_deref().unwrap_or_default()
+    }
+}
+
+/// An entry that owns some of the files modified by a PR.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum OwnersEntry<'a> {
+    /// A path in `assign.owners`.
+    Config(&'a str),
+    /// A rule of the repository's CODEOWNERS file.
+    CodeOwners(&'a codeowners::Rule),
+}
+
+impl fmt::Display for OwnersEntry<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OwnersEntry::Config(pattern) => write!(f, "`{pattern}`"),
+            OwnersEntry::CodeOwners(rule) => rule.fmt(f),
+        }
+    }
+}
+
 /// Input for auto-assignment when a PR is created.
 pub(super) struct AssignInput {
     git_diff: String,
@@ -88,25 +202,24 @@ pub(super) struct AssignInput {
 pub(super) async fn parse_input(
     ctx: &Context,
     event: &IssuesEvent,
+    pr: &PullRequestData<'_>,
     config: Option<&AssignConfig>,
 ) -> Result<Option<AssignInput>, String> {
     let config = match config {
         Some(config) => config,
         None => return Ok(None),
     };
-    if config.owners.is_empty()
-        || !matches!(event.action, IssuesAction::Opened)
-        || !event.issue.is_pr()
+    if !config.has_owners() || !matches!(event.action, IssuesAction::Opened) || !event.issue.is_pr()
     {
         return Ok(None);
     }
-    let git_diff = match event.issue.diff(&ctx.github).await {
+    let git_diff = match pr.diff(&ctx.github).await {
         Ok(None) => return Ok(None),
         Err(e) => {
             log::error!("failed to fetch diff: {:?}", e);
             return Ok(None);
         }
-        Ok(Some(diff)) => diff,
+        Ok(Some(diff)) => diff.to_string(),
     };
     Ok(Some(AssignInput { git_diff }))
 }
@@ -121,8 +234,8 @@ pub(super) async fn handle_input(
 ) -> anyhow::Result<()> {
     // Don't auto-assign or welcome if the user manually set the assignee when opening.
     if event.issue.assignees.is_empty() {
-        let (assignee, from_comment) = determine_assignee(ctx, event, config, &input).await?;
-        if assignee.as_deref() == Some("ghost") {
+        let (assignees, from_comment) = determine_assignee(ctx, event, config, &input).await?;
+        if assignees.iter().any(|assignee| assignee == "ghost") {
             // "ghost" is GitHub's placeholder account for deleted accounts.
             // It is used here as a convenient way to prevent assignment. This
             // is typically used for rollups or experiments where you don't
@@ -134,9 +247,10 @@ pub(super) async fn handle_input(
             .is_new_contributor(&event.repository, &event.issue.user.login)
             .await
         {
-            let who_text = match &assignee {
-                Some(assignee) => WELCOME_WITH_REVIEWER.replace("{assignee}", assignee),
-                None => WELCOME_WITHOUT_REVIEWER.to_string(),
+            let who_text = if assignees.is_empty() {
+                WELCOME_WITHOUT_REVIEWER.to_string()
+            } else {
+                WELCOME_WITH_REVIEWER.replace("{assignee}", &mentions(&assignees))
             };
             let mut welcome = NEW_USER_WELCOME_MESSAGE.replace("{who}", &who_text);
             if let Some(contrib) = &config.contributing_url {
@@ -145,18 +259,19 @@ pub(super) async fn handle_input(
             }
             Some(welcome)
         } else if !from_comment {
-            let welcome = match &assignee {
-                Some(assignee) => RETURNING_USER_WELCOME_MESSAGE.replace("{assignee}", assignee),
-                None => RETURNING_USER_WELCOME_MESSAGE_NO_REVIEWER
-                    .replace("{author}", &event.issue.user.login),
+            let welcome = if assignees.is_empty() {
+                RETURNING_USER_WELCOME_MESSAGE_NO_REVIEWER
+                    .replace("{author}", &event.issue.user.login)
+            } else {
+                RETURNING_USER_WELCOME_MESSAGE.replace("{assignee}", &mentions(&assignees))
             };
             Some(welcome)
         } else {
             // No welcome is posted if they are not new and they used `r?` in the opening body.
             None
         };
-        if let Some(assignee) = assignee {
-            set_assignee(&event.issue, &ctx.github, &assignee).await;
+        if !assignees.is_empty() {
+            set_reviewers(&event.issue, &ctx.github, config.review_mode, &assignees).await;
         }
 
         if let Some(welcome) = welcome {
@@ -188,11 +303,11 @@ pub(super) async fn handle_input(
 
 /// Finds the `r?` command in the PR body.
 ///
-/// Returns the name after the `r?` command, or None if not found.
-fn find_assign_command(ctx: &Context, event: &IssuesEvent) -> Option<String> {
+/// Returns the names after the `r?` command, or None if not found.
+fn find_assign_command(ctx: &Context, event: &IssuesEvent) -> Option<Vec<String>> {
     let mut input = Input::new(&event.issue.body, vec![&ctx.username]);
     input.find_map(|command| match command {
-        Command::Assign(Ok(AssignCommand::ReviewName { name })) => Some(name),
+        Command::Assign(Ok(AssignCommand::ReviewName { names })) => Some(names),
         _ => None,
     })
 }
@@ -225,63 +340,228 @@ fn modifies_submodule(diff: &str) -> Option<String> {
     }
 }
 
-/// Sets the assignee of a PR, alerting any errors.
-async fn set_assignee(issue: &Issue, github: &GithubClient, username: &str) {
+/// Sets the reviewers of a PR, alerting any errors.
+///
+/// Depending on `assign.review_mode`, the reviewers are assigned to the PR,
+/// have their review requested, or both. Other assignees and pending review
+/// requests are removed.
+pub(super) async fn set_reviewers(
+    issue: &Issue,
+    github: &GithubClient,
+    mode: ReviewMode,
+    usernames: &[String],
+) {
+    if mode.assigns() {
+        assign_reviewers(issue, github, usernames).await;
+    }
+    if mode.requests_review() {
+        request_reviews(issue, github, usernames).await;
+    }
+}
+
+/// Assigns reviewers to a PR, alerting any errors.
+async fn assign_reviewers(issue: &Issue, github: &GithubClient, usernames: &[String]) {
     // Don't re-assign if already assigned, e.g. on comment edit
-    if issue.contain_assignee(&username) {
+    if usernames
+        .iter()
+        .all(|username| issue.contain_assignee(username))
+    {
         log::trace!(
-            "ignoring assign PR {} to {}, already assigned",
+            "ignoring assign PR {} to {:?}, already assigned",
             issue.global_id(),
-            username,
+            usernames,
         );
         return;
     }
-    if let Err(err) = issue.set_assignee(github, &username).await {
+    let mut assigned = false;
+    for username in usernames {
+        if issue.contain_assignee(username) {
+            assigned = true;
+            continue;
+        }
+        match issue.add_assignee(github, username).await {
+            Ok(()) => assigned = true,
+            Err(err) => {
+                log::warn!(
+                    "failed to set assignee of PR {} to {}: {:?}",
+                    issue.global_id(),
+                    username,
+                    err
+                );
+                if let Err(e) = issue
+                    .post_comment(
+                        github,
+                        &format!(
+                            "Failed to set assignee to `{username}`: {err}\n\
+                             \n\
+                             > **Note**: Only org members with at least the repository \"read\" role, \
+                               users with write permissions, or people who have commented on the PR may \
+                               be assigned."
+                        ),
+                    )
+                    .await
+                {
+                    log::warn!("failed to post error comment: {e}");
+                }
+            }
+        }
+    }
+    // Keep the previous assignees if nobody could be assigned.
+    if !assigned {
+        return;
+    }
+    for assignee in &issue.assignees {
+        if usernames
+            .iter()
+            .any(|username| username.to_lowercase() == assignee.login.to_lowercase())
+        {
+            continue;
+        }
+        if let Err(e) = issue
+            .remove_assignees(github, Selection::One(&assignee.login))
+            .await
+        {
+            log::warn!(
+                "failed to remove assignee {} of PR {}: {e:?}",
+                assignee.login,
+                issue.global_id()
+            );
+        }
+    }
+}
+
+/// Requests reviews of a PR, alerting any errors.
+///
+/// Any other pending review requests are removed, so that the requested
+/// reviewers stay in sync with the assignees.
+async fn request_reviews(issue: &Issue, github: &GithubClient, usernames: &[String]) {
+    let requested = requested_reviewers(issue, github).await;
+    let is_requested = |name: &str| requested.iter().any(|r| r.eq_ignore_ascii_case(name));
+    // GitHub doesn't allow requesting a review from the PR author.
+    let new: Vec<&str> = usernames
+        .iter()
+        .map(|username| username.as_str())
+        .filter(|username| !is_requested(username) && !is_self_assign(username, &issue.user.login))
+        .collect();
+    if new.is_empty() {
+        log::trace!(
+            "not requesting a review of PR {} from {:?}, already requested",
+            issue.global_id(),
+            usernames,
+        );
+    } else if let Err(err) = issue.request_reviewers(github, &new).await {
         log::warn!(
-            "failed to set assignee of PR {} to {}: {:?}",
+            "failed to request review of PR {} from {:?}: {:?}",
             issue.global_id(),
-            username,
+            new,
             err
         );
         if let Err(e) = issue
             .post_comment(
                 github,
                 &format!(
-                    "Failed to set assignee to `{username}`: {err}\n\
+                    "Failed to request a review from `{}`: {err}\n\
                      \n\
-                     > **Note**: Only org members with at least the repository \"read\" role, \
-                       users with write permissions, or people who have commented on the PR may \
-                       be assigned."
+                     > **Note**: Reviews can only be requested from users with access to the \
+                       repository.",
+                    new.join(",")
                 ),
             )
             .await
         {
             log::warn!("failed to post error comment: {e}");
         }
+        return;
+    }
+    let others: Vec<&str> = requested
+        .iter()
+        .map(|r| r.as_str())
+        .filter(|r| {
+            !usernames
+                .iter()
+                .any(|username| username.eq_ignore_ascii_case(r))
+        })
+        .collect();
+    if !others.is_empty() {
+        if let Err(e) = issue.remove_requested_reviewers(github, &others).await {
+            log::warn!(
+                "failed to remove review requests of PR {}: {e:?}",
+                issue.global_id()
+            );
+        }
+    }
+}
+
+/// Returns the users whose review of a PR is pending.
+///
+/// The payload of comment events doesn't include them, so they are fetched,
+/// falling back to the payload if that fails.
+async fn requested_reviewers(issue: &Issue, github: &GithubClient) -> Vec<String> {
+    match issue.get_requested_reviewers(github).await {
+        Ok(users) => users.into_iter().map(|user| user.login).collect(),
+        Err(e) => {
+            log::warn!(
+                "failed to get requested reviewers of PR {}: {e:?}",
+                issue.global_id()
+            );
+            issue
+                .requested_reviewers
+                .iter()
+                .map(|user| user.login.clone())
+                .collect()
+        }
+    }
+}
+
+/// Handles `release-assignment` on a PR whose reviewers have their review
+/// requested, removing the user's review request and assignment.
+async fn release_reviewer(
+    issue: &Issue,
+    github: &GithubClient,
+    mode: ReviewMode,
+    username: &str,
+) -> anyhow::Result<()> {
+    let requested = requested_reviewers(issue, github).await;
+    if requested.iter().any(|r| r.eq_ignore_ascii_case(username)) {
+        issue
+            .remove_requested_reviewers(github, &[username])
+            .await?;
     }
+    if mode.assigns() && issue.contain_assignee(username) {
+        issue
+            .remove_assignees(github, Selection::One(username))
+            .await?;
+    }
+    Ok(())
 }
 
 /// Determines who to assign the PR to based on either an `r?` command, or
 /// based on which files were modified.
 ///
-/// Returns `(assignee, from_comment)` where `assignee` is who to assign to
-/// (or None if no assignee could be found). `from_comment` is a boolean
-/// indicating if the assignee came from an `r?` command (it is false if
+/// Returns `(assignees, from_comment)` where `assignees` are who to assign
+/// to (empty if no assignee could be found). `from_comment` is a boolean
+/// indicating if the assignees came from an `r?` command (it is false if
 /// determined from the diff).
 async fn determine_assignee(
     ctx: &Context,
     event: &IssuesEvent,
     config: &AssignConfig,
     input: &AssignInput,
-) -> anyhow::Result<(Option<String>, bool)> {
+) -> anyhow::Result<(Vec<String>, bool)> {
     let teams = crate::team_data::teams(&ctx.github).await?;
-    if let Some(name) = find_assign_command(ctx, event) {
-        if is_self_assign(&name, &event.issue.user.login) {
-            return Ok((Some(name.to_string()), true));
-        }
+    if let Some(names) = find_assign_command(ctx, event) {
         // User included `r?` in the opening PR body.
-        match find_reviewer_from_names(&teams, config, &event.issue, &[name]) {
-            Ok(assignee) => return Ok((Some(assignee), true)),
+        match find_requested_reviewers(
+            ctx,
+            &teams,
+            config,
+            &event.issue,
+            &event.issue.user.login,
+            &names,
+        )
+        .await
+        {
+            Ok(assignees) => return Ok((assignees, true)),
             Err(e) => {
                 event
                     .issue
@@ -291,24 +571,49 @@ async fn determine_assignee(
             }
         }
     }
+    // Why the candidates from the diff couldn't be assigned, to let the
+    // author know if the fallback group doesn't work either.
+    let mut filtered = None;
     // Errors fall-through to try fallback group.
-    match find_reviewers_from_diff(config, &input.git_diff) {
-        Ok(candidates) if !candidates.is_empty() => {
-            match find_reviewer_from_names(&teams, config, &event.issue, &candidates) {
-                Ok(assignee) => return Ok((Some(assignee), false)),
+    let codeowners = load_codeowners(ctx, config, &event.issue).await;
+    match find_reviewers_from_diff(
+        config,
+        codeowners.as_ref(),
+        &input.git_diff,
+        &mut Explanation::disabled(),
+    ) {
+        Ok((candidates, count)) if !candidates.is_empty() => {
+            match find_reviewers_from_names(
+                ctx,
+                &teams,
+                config,
+                &event.issue,
+                &candidates,
+                count,
+                &[],
+            )
+            .await
+            {
+                Ok(assignees) => return Ok((assignees, false)),
                 Err(FindReviewerError::TeamNotFound(team)) => log::warn!(
                     "team {team} not found via diff from PR {}, \
                     is there maybe a misconfigured group?",
                     event.issue.global_id()
                 ),
-                // TODO: post a comment on the PR if the reviewers were filtered due to being on vacation
-                Err(
-                    e @ FindReviewerError::NoReviewer { .. }
-                    | e @ FindReviewerError::AllReviewersFiltered { .. },
-                ) => log::trace!(
+                Err(e @ FindReviewerError::NoReviewer { .. }) => log::trace!(
                     "no reviewer could be determined for PR {}: {e}",
                     event.issue.global_id()
                 ),
+                Err(
+                    e @ FindReviewerError::AllReviewersFiltered { .. }
+                    | e @ FindReviewerError::ReviewersUnavailable { .. },
+                ) => {
+                    log::trace!(
+                        "no reviewer could be determined for PR {}: {e}",
+                        event.issue.global_id()
+                    );
+                    filtered = Some(e);
+                }
             }
         }
         // If no owners matched the diff, fall-through.
@@ -322,8 +627,8 @@ async fn determine_assignee(
     }
 
     if let Some(fallback) = config.adhoc_groups.get("fallback") {
-        match find_reviewer_from_names(&teams, config, &event.issue, fallback) {
-            Ok(assignee) => return Ok((Some(assignee), false)),
+        match find_reviewers_from_names(ctx, &teams, config, &event.issue, fallback, 1, &[]).await {
+            Ok(assignees) => return Ok((assignees, false)),
             Err(e) => {
                 log::trace!(
                     "failed to select from fallback group for PR {}: {e}",
@@ -332,18 +637,57 @@ async fn determine_assignee(
             }
         }
     }
-    Ok((None, false))
+    if let Some(e) = filtered {
+        event
+            .issue
+            .post_comment(&ctx.github, &e.to_string())
+            .await?;
+    }
+    Ok((Vec::new(), false))
 }
 
-/// Returns a list of candidate reviewers to use based on which files were changed.
+/// Fetches the CODEOWNERS file of the PR's repository if `assign.codeowners`
+/// is enabled.
+///
+/// Errors are logged, so that reviewers can still be found from `owners`.
+pub(super) async fn load_codeowners(
+    ctx: &Context,
+    config: &AssignConfig,
+    issue: &Issue,
+) -> Option<CodeOwners> {
+    if !config.codeowners {
+        return None;
+    }
+    match codeowners::fetch(&ctx.github, issue.repository(), config).await {
+        Ok(codeowners) => codeowners,
+        Err(e) => {
+            log::warn!(
+                "failed to fetch CODEOWNERS for {}: {e:?}",
+                issue.repository()
+            );
+            None
+        }
+    }
+}
+
+/// Returns a list of candidate reviewers to use based on which files were
+/// changed, along with how many of them should be assigned.
+///
+/// Files that don't match any `owners` path are looked up in `codeowners`,
+/// if given.
 ///
 /// May return an error if the owners map is misconfigured.
 ///
 /// Beware this may return an empty list if nothing matches.
-fn find_reviewers_from_diff(config: &AssignConfig, diff: &str) -> anyhow::Result<Vec<String>> {
+pub(super) fn find_reviewers_from_diff(
+    config: &AssignConfig,
+    codeowners: Option<&CodeOwners>,
+    diff: &str,
+    explanation: &mut Explanation,
+) -> anyhow::Result<(Vec<String>, usize)> {
     // Map of `owners` path to the number of changes found in that path.
     // This weights the reviewer choice towards places where the most edits are done.
-    let mut counts: HashMap<&str, u32> = HashMap::new();
+    let mut counts: HashMap<OwnersEntry<'_>, u32> = HashMap::new();
     // List of the longest `owners` patterns that match the current path. This
     // prefers choosing reviewers from deeply nested paths over those defined
     // for top-level paths, under the assumption that they are more
@@ -352,6 +696,8 @@ fn find_reviewers_from_diff(config: &AssignConfig, diff: &str) -> anyhow::Result
     // This is a list to handle the situation if multiple paths of the same
     // length match.
     let mut longest_owner_patterns = Vec::new();
+    // The entries matched by each file, for the explanation.
+    let mut files = Vec::new();
     // Iterate over the diff, finding the start of each file. After each file
     // is found, it counts the number of modified lines in that file, and
     // tracks those in the `counts` map.
@@ -379,12 +725,25 @@ fn find_reviewers_from_diff(config: &AssignConfig, diff: &str) -> anyhow::Result
                 longest
                     .iter()
                     .filter(|(_, count)| **count == max_count)
-                    .map(|x| *x.0),
+                    .map(|x| OwnersEntry::Config(x.0)),
             );
+            longest_owner_patterns.sort();
+            if longest_owner_patterns.is_empty() {
+                if let Some(rule) = codeowners.and_then(|c| c.rule_for(path)) {
+                    longest_owner_patterns.push(OwnersEntry::CodeOwners(rule));
+                }
+            }
+            if explanation.is_enabled() {
+                let entries: Vec<_> = longest_owner_patterns
+                    .iter()
+                    .map(|e| e.to_string())
+                    .collect();
+                files.push((path, (!entries.is_empty()).then(|| entries.join(", "))));
+            }
             // Give some weight to these patterns to start. This helps with
             // files modified without any lines changed.
             for owner_pattern in &longest_owner_patterns {
-                *counts.entry(owner_pattern).or_default() += 1;
+                *counts.entry(*owner_pattern).or_default() += 1;
             }
             continue;
         }
@@ -393,25 +752,60 @@ fn find_reviewers_from_diff(config: &AssignConfig, diff: &str) -> anyhow::Result
             || (!line.starts_with("---") && line.starts_with('-'))
         {
             for owner_path in &longest_owner_patterns {
-                *counts.entry(owner_path).or_default() += 1;
+                *counts.entry(*owner_path).or_default() += 1;
             }
         }
     }
+    explanation.note_files(&files);
+    explanation.note(|| {
+        let mut counts: Vec<_> = counts.iter().collect();
+        counts.sort();
+        let counts: Vec<_> = counts
+            .iter()
+            .map(|(entry, count)| format!("{entry} ({count})"))
+            .collect();
+        format!(
+            "Modified lines (plus one per file) by owners entry: {}",
+            if counts.is_empty() {
+                "none".to_string()
+            } else {
+                counts.join(", ")
+            }
+        )
+    });
     // Use the `owners` entry with the most number of modifications.
     let max_count = counts.values().copied().max().unwrap_or(0);
     let max_paths = counts
         .iter()
         .filter(|(_, count)| **count == max_count)
         .map(|(path, _)| path);
-    let mut potential: Vec<_> = max_paths
-        .flat_map(|owner_path| &config.owners[*owner_path])
-        .map(|owner| owner.to_string())
-        .collect();
-    // Dedupe. This isn't strictly necessary, as `find_reviewer_from_names` will deduplicate.
+    let mut potential = Vec::new();
+    let mut count = 1;
+    for entry in max_paths {
+        match entry {
+            OwnersEntry::Config(path) => {
+                let owners = &config.owners[*path];
+                potential.extend(owners.reviewers().iter().cloned());
+                count = count.max(owners.min_reviewers());
+            }
+            OwnersEntry::CodeOwners(rule) => potential.extend(rule.owners.iter().cloned()),
+        }
+    }
+    // Dedupe. This isn't strictly necessary, as `find_reviewers_from_names` will deduplicate.
     // However, this helps with testing.
     potential.sort();
     potential.dedup();
-    Ok(potential)
+    explanation.note(|| {
+        if potential.is_empty() {
+            "No owners entry matches the diff".to_string()
+        } else {
+            format!(
+                "Choosing {count} reviewer(s) from the most modified entries: {}",
+                code_list(&potential)
+            )
+        }
+    });
+    Ok((potential, count))
 }
 
 /// Handles a command posted in a comment.
@@ -443,8 +837,8 @@ pub(super) async fn handle_command(
                 .await?;
             return Ok(());
         }
-        let username = match cmd {
-            AssignCommand::Own => event.user().login.clone(),
+        let usernames = match cmd {
+            AssignCommand::Own => vec![event.user().login.clone()],
             AssignCommand::User { username } => {
                 // Allow users on vacation to assign themselves to a PR, but not anyone else.
                 if config.is_on_vacation(&username)
@@ -456,17 +850,25 @@ pub(super) async fn handle_command(
                         .await?;
                     return Ok(());
                 }
-                username
+                vec![username]
             }
             AssignCommand::Release => {
+                if config.review_mode.requests_review() {
+                    release_reviewer(issue, &ctx.github, config.review_mode, &event.user().login)
+                        .await?;
+                    return Ok(());
+                }
                 log::trace!(
                     "ignoring release on PR {:?}, must always have assignee",
                     issue.global_id()
                 );
                 return Ok(());
             }
-            AssignCommand::ReviewName { name } => {
-                if config.owners.is_empty() {
+            AssignCommand::ReviewExplain => {
+                return explain_reviewer_selection(ctx, config, event, issue).await;
+            }
+            AssignCommand::ReviewName { names } => {
+                if !config.has_owners() {
                     // To avoid conflicts with the highfive bot while transitioning,
                     // r? is ignored if `owners` is not configured in triagebot.toml.
                     return Ok(());
@@ -483,21 +885,26 @@ pub(super) async fn handle_command(
                     // welcome message).
                     return Ok(());
                 }
-                if is_self_assign(&name, &event.user().login) {
-                    name.to_string()
-                } else {
-                    let teams = crate::team_data::teams(&ctx.github).await?;
-                    match find_reviewer_from_names(&teams, config, issue, &[name]) {
-                        Ok(assignee) => assignee,
-                        Err(e) => {
-                            issue.post_comment(&ctx.github, &e.to_string()).await?;
-                            return Ok(());
-                        }
+                let teams = crate::team_data::teams(&ctx.github).await?;
+                match find_requested_reviewers(
+                    ctx,
+                    &teams,
+                    config,
+                    issue,
+                    &event.user().login,
+                    &names,
+                )
+                .await
+                {
+                    Ok(assignees) => assignees,
+                    Err(e) => {
+                        issue.post_comment(&ctx.github, &e.to_string()).await?;
+                        return Ok(());
                     }
                 }
             }
         };
-        set_assignee(issue, &ctx.github, &username).await;
+        set_reviewers(issue, &ctx.github, config.review_mode, &usernames).await;
         return Ok(());
     }
 
@@ -538,7 +945,9 @@ pub(super) async fn handle_command(
                 }
             };
         }
-        AssignCommand::ReviewName { .. } => bail!("r? is only allowed on PRs."),
+        AssignCommand::ReviewName { .. } | AssignCommand::ReviewExplain => {
+            bail!("r? is only allowed on PRs.")
+        }
     };
     // Don't re-assign if aleady assigned, e.g. on comment edit
     if issue.contain_assignee(&to_assign) {
@@ -575,8 +984,93 @@ pub(super) async fn handle_command(
     Ok(())
 }
 
+/// Handles `r? explain`, posting how a reviewer would be chosen for the PR
+/// if it were opened now.
+async fn explain_reviewer_selection(
+    ctx: &Context,
+    config: &AssignConfig,
+    event: &Event,
+    issue: &Issue,
+) -> anyhow::Result<()> {
+    let mut explanation = Explanation::enabled();
+    let mut names = Vec::new();
+    let mut count = 1;
+    // The issue in a comment's payload has no base and head to diff.
+    let pr = event.repo().get_pr(&ctx.github, issue.number).await?;
+    match PullRequestData::new(&pr).diff(&ctx.github).await? {
+        Some(diff) => {
+            let codeowners = load_codeowners(ctx, config, issue).await;
+            match find_reviewers_from_diff(config, codeowners.as_ref(), diff, &mut explanation) {
+                Ok(found) => (names, count) = found,
+                Err(e) => explanation.note(|| format!("The owners map is misconfigured: {e}")),
+            }
+        }
+        None => explanation.note(|| "The diff of this PR is not available".to_string()),
+    }
+    if names.is_empty() {
+        if let Some(fallback) = config.adhoc_groups.get("fallback") {
+            explanation.note(|| "Falling back to the `fallback` group".to_string());
+            names = fallback.clone();
+        }
+    }
+    if !names.is_empty() {
+        let teams = crate::team_data::teams(&ctx.github).await?;
+        match candidate_reviewers_from_names(&teams, config, issue, &names, &mut explanation) {
+            Ok(candidates) => {
+                let mut candidates: Vec<&str> = candidates.into_iter().collect();
+                candidates.sort();
+                let (loads, prefs) = reviewer_status(ctx, &candidates).await.unwrap_or_else(|e| {
+                    log::warn!("failed to get reviewer status: {e:?}");
+                    (HashMap::new(), HashMap::new())
+                });
+                let today = chrono::Utc::today().naive_utc();
+                for name in &candidates {
+                    let key = name.to_lowercase();
+                    if let Some(reason) =
+                        unavailable_reason(prefs.get(&key), loads.get(&key), today)
+                    {
+                        explanation.note(|| format!("`{name}` is unavailable: {reason}"));
+                    }
+                }
+                let selection = match config.selection {
+                    ReviewerSelection::Random => "random",
+                    ReviewerSelection::RoundRobin => "round-robin",
+                    ReviewerSelection::LeastLoaded => "least-loaded",
+                };
+                explanation.note(|| {
+                    format!(
+                        "Candidates: {}, picked with the `{selection}` selection",
+                        code_list(&candidates),
+                    )
+                });
+            }
+            Err(e) => explanation.note(|| format!("No reviewer can be picked: {e}")),
+        }
+    }
+    let mut comment = format!("Reviewer selection for this PR (up to {count} reviewer(s)):\n\n");
+    for line in explanation.lines() {
+        let line = format!("- {line}\n");
+        if comment.len() + line.len() > MAX_COMMENT_LEN - 20 {
+            comment.push_str("- ...\n");
+            break;
+        }
+        comment.push_str(&line);
+    }
+    issue.post_comment(&ctx.github, &comment).await?;
+    Ok(())
+}
+
+/// Formats a list of names as inline code, like `` `a`, `b` ``.
+fn code_list(items: &[impl fmt::Display]) -> String {
+    items
+        .iter()
+        .map(|item| format!("`{item}`"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 #[derive(PartialEq, Debug)]
-enum FindReviewerError {
+pub(super) enum FindReviewerError {
     /// User specified something like `r? foo/bar` where that team name could
     /// not be found.
     TeamNotFound(String),
@@ -594,6 +1088,12 @@ enum FindReviewerError {
         initial: Vec<String>,
         filtered: Vec<String>,
     },
+    /// All potential candidates are away or at their review capacity, as
+    /// set through Zulip. `unavailable` describes each of them.
+    ReviewersUnavailable {
+        initial: Vec<String>,
+        unavailable: Vec<String>,
+    },
 }
 
 impl std::error::Error for FindReviewerError {}
@@ -630,47 +1130,291 @@ impl fmt::Display for FindReviewerError {
                     filtered.join(","),
                 )
             }
+            FindReviewerError::ReviewersUnavailable {
+                initial,
+                unavailable,
+            } => {
+                write!(
+                    f,
+                    "Could not assign reviewer from: `{}`.\n\
+                     All candidates are currently unavailable for reviews:\n",
+                    initial.join(","),
+                )?;
+                for reviewer in unavailable {
+                    writeln!(f, "- {reviewer}")?;
+                }
+                write!(f, "\nUse r? to specify someone else to assign.")
+            }
         }
     }
 }
 
-/// Finds a reviewer to assign to a PR.
+/// Finds the reviewers for the names of an `r?` command posted by
+/// `requester`, one for each distinct name.
+///
+/// Requesters can always assign themselves, which is used, for example, when
+/// authors want to handle their own rollups.
+async fn find_requested_reviewers(
+    ctx: &Context,
+    teams: &Teams,
+    config: &AssignConfig,
+    issue: &Issue,
+    requester: &str,
+    names: &[String],
+) -> Result<Vec<String>, FindReviewerError> {
+    let mut reviewers: Vec<String> = Vec::new();
+    for name in dedup_names(names) {
+        if is_self_assign(name, requester) {
+            if !reviewers.iter().any(|r| r.eq_ignore_ascii_case(name)) {
+                reviewers.push(name.clone());
+            }
+            continue;
+        }
+        let found = find_reviewers_from_names(
+            ctx,
+            teams,
+            config,
+            issue,
+            std::slice::from_ref(name),
+            1,
+            &reviewers,
+        )
+        .await?;
+        reviewers.extend(found);
+    }
+    Ok(reviewers)
+}
+
+/// Removes the repeated names of an `r?` command, ignoring case.
+fn dedup_names(names: &[String]) -> Vec<&String> {
+    let mut seen = HashSet::new();
+    names
+        .iter()
+        .filter(|name| seen.insert(name.to_lowercase()))
+        .collect()
+}
+
+/// Finds up to `count` distinct reviewers to assign to a PR.
 ///
 /// The `names` is a list of candidate reviewers `r?`, such as `compiler` or
 /// `@octocat`, or names from the owners map. It can contain GitHub usernames,
 /// auto-assign groups, or rust-lang team names. It must have at least one
-/// entry.
-fn find_reviewer_from_names(
+/// entry. Reviewers in `exclude` are not picked.
+///
+/// Fewer than `count` reviewers are returned if there aren't enough
+/// candidates, but always at least one.
+pub(super) async fn find_reviewers_from_names(
+    ctx: &Context,
     teams: &Teams,
     config: &AssignConfig,
     issue: &Issue,
     names: &[String],
-) -> Result<String, FindReviewerError> {
-    let candidates = candidate_reviewers_from_names(teams, config, issue, names)?;
-    // This uses a relatively primitive random choice algorithm.
-    // GitHub's CODEOWNERS supports much more sophisticated options, such as:
-    //
-    // - Round robin: Chooses reviewers based on who's received the least
-    //   recent review request, focusing on alternating between all members of
-    //   the team regardless of the number of outstanding reviews they
-    //   currently have.
-    // - Load balance: Chooses reviewers based on each member's total number
-    //   of recent review requests and considers the number of outstanding
-    //   reviews for each member. The load balance algorithm tries to ensure
-    //   that each team member reviews an equal number of pull requests in any
-    //   30 day period.
-    //
-    // Additionally, with CODEOWNERS, users marked as "Busy" in the GitHub UI
-    // will not be selected for reviewer. There are several other options for
-    // configuring CODEOWNERS as well.
-    //
-    // These are all ideas for improving the selection here. However, I'm not
-    // sure they are really worth the effort.
-    Ok(candidates
+    count: usize,
+    exclude: &[String],
+) -> Result<Vec<String>, FindReviewerError> {
+    let mut candidates =
+        candidate_reviewers_from_names(teams, config, issue, names, &mut Explanation::disabled())?;
+    if !exclude.is_empty() {
+        candidates.retain(|name| {
+            !exclude
+                .iter()
+                .any(|excluded| excluded.to_lowercase() == name.to_lowercase())
+        });
+        if candidates.is_empty() {
+            return Err(FindReviewerError::AllReviewersFiltered {
+                initial: names.to_vec(),
+                filtered: exclude.to_vec(),
+            });
+        }
+    }
+    let reviewers: Vec<&str> = candidates.iter().copied().collect();
+    // Fall back to choosing among all candidates rather than failing the
+    // assignment.
+    let (loads, prefs) = reviewer_status(ctx, &reviewers).await.unwrap_or_else(|e| {
+        log::warn!(
+            "failed to get reviewer status for PR {}: {e:?}",
+            issue.global_id()
+        );
+        (HashMap::new(), HashMap::new())
+    });
+
+    let today = chrono::Utc::today().naive_utc();
+    let mut unavailable = Vec::new();
+    let available: HashSet<&str> = candidates
         .into_iter()
-        .choose(&mut rand::thread_rng())
+        .filter(|name| {
+            let key = name.to_lowercase();
+            match unavailable_reason(prefs.get(&key), loads.get(&key), today) {
+                Some(reason) => {
+                    unavailable.push(format!("{name} ({reason})"));
+                    false
+                }
+                None => true,
+            }
+        })
+        .collect();
+    if available.is_empty() {
+        unavailable.sort();
+        return Err(FindReviewerError::ReviewersUnavailable {
+            initial: names.to_vec(),
+            unavailable,
+        });
+    }
+    let selected = select_reviewers(available, count, config.selection, &loads);
+    if selected.len() < count {
+        log::info!(
+            "only found {} of {count} reviewers for PR {}",
+            selected.len(),
+            issue.global_id()
+        );
+    }
+    Ok(selected)
+}
+
+/// Picks up to `count` distinct reviewers among the available ones, one at a
+/// time with [`select_reviewer`].
+fn select_reviewers(
+    mut available: HashSet<&str>,
+    count: usize,
+    selection: ReviewerSelection,
+    loads: &HashMap<String, ReviewerLoad>,
+) -> Vec<String> {
+    let mut selected = Vec::new();
+    while selected.len() < count && !available.is_empty() {
+        let reviewer = select_reviewer(&available, selection, loads);
+        available.remove(reviewer);
+        selected.push(reviewer.to_string());
+    }
+    selected
+}
+
+/// Returns the current workload and the preferences of the given reviewers.
+async fn reviewer_status(
+    ctx: &Context,
+    reviewers: &[&str],
+) -> anyhow::Result<(HashMap<String, ReviewerLoad>, HashMap<String, ReviewPrefs>)> {
+    let since = chrono::Utc::now() - chrono::Duration::days(RECENT_ASSIGNMENT_DAYS);
+    let db = ctx.db.try_get().await?;
+    let loads = review_assignments::reviewer_loads(&db, reviewers, since).await?;
+    let prefs = review_prefs::prefs_by_username(&db, reviewers).await?;
+    Ok((loads, prefs))
+}
+
+/// Returns why a reviewer can't be assigned right now, based on the
+/// availability they set through Zulip.
+fn unavailable_reason(
+    prefs: Option<&ReviewPrefs>,
+    load: Option<&ReviewerLoad>,
+    today: chrono::NaiveDate,
+) -> Option<String> {
+    let prefs = prefs?;
+    if prefs.is_away(today) {
+        let until = prefs.away_until.unwrap();
+        return Some(format!("away until {until}"));
+    }
+    let open = load.map_or(0, |load| load.open);
+    match prefs.max_assigned_prs {
+        Some(max) if open >= i64::from(max) => {
+            Some(format!("at capacity with {open} assigned PRs"))
+        }
+        _ => None,
+    }
+}
+
+/// Chooses one of the candidates according to the selection strategy.
+///
+/// `loads` is keyed by lowercase username; candidates that are missing from
+/// it have never been assigned. Ties are broken randomly.
+fn select_reviewer<'a>(
+    candidates: &HashSet<&'a str>,
+    selection: ReviewerSelection,
+    loads: &HashMap<String, ReviewerLoad>,
+) -> &'a str {
+    let mut candidates: Vec<&str> = candidates.iter().copied().collect();
+    // Sort so that the set of tied candidates doesn't depend on hash order.
+    candidates.sort();
+    let load = |name: &str| loads.get(&name.to_lowercase()).cloned().unwrap_or_default();
+    let best: Vec<&str> = match selection {
+        ReviewerSelection::Random => candidates,
+        ReviewerSelection::RoundRobin => min_by_key(candidates, |name| load(name).last_assigned_at),
+        ReviewerSelection::LeastLoaded => min_by_key(candidates, |name| {
+            let load = load(name);
+            (load.open, load.recent)
+        }),
+    };
+    best.choose(&mut rand::thread_rng())
         .expect("candidate_reviewers_from_names always returns at least one entry")
-        .to_string())
+}
+
+/// Returns all the candidates with the smallest key.
+fn min_by_key<K: Ord>(candidates: Vec<&str>, key: impl Fn(&str) -> K) -> Vec<&str> {
+    let Some(min) = candidates.iter().map(|c| key(c)).min() else {
+        return candidates;
+    };
+    candidates.into_iter().filter(|c| key(c) == min).collect()
+}
+
+/// Returns the reviewers of a PR: its assignees, or the users whose review
+/// was requested with the `review-request` review mode.
+pub(super) fn reviewers(mode: ReviewMode, issue: &Issue) -> &[github::User] {
+    if mode.assigns() {
+        &issue.assignees
+    } else {
+        &issue.requested_reviewers
+    }
+}
+
+/// Records assignment changes of PRs in the `review_assignments` table.
+///
+/// With the `review-request` review mode, review requests are tracked
+/// instead of assignees.
+pub(super) async fn track_assignments(
+    ctx: &Context,
+    config: &AssignConfig,
+    event: &IssuesEvent,
+) -> anyhow::Result<()> {
+    if !event.issue.is_pr() {
+        return Ok(());
+    }
+    let repo = event.issue.repository().to_string();
+    let number = event.issue.number as i32;
+    let (assigned, unassigned, user) = if config.review_mode.assigns() {
+        (
+            IssuesAction::Assigned,
+            IssuesAction::Unassigned,
+            &event.assignee,
+        )
+    } else {
+        (
+            IssuesAction::ReviewRequested,
+            IssuesAction::ReviewRequestRemoved,
+            &event.requested_reviewer,
+        )
+    };
+    match (&event.action, user) {
+        (action, Some(user)) if *action == assigned => {
+            let db = ctx.db.get().await;
+            review_assignments::record_assignment(&db, &repo, number, &user.login).await?;
+        }
+        (action, Some(user)) if *action == unassigned => {
+            let db = ctx.db.get().await;
+            review_assignments::record_unassignment(&db, &repo, number, &user.login).await?;
+        }
+        (IssuesAction::Closed, _) => {
+            let db = ctx.db.get().await;
+            review_assignments::record_closed(&db, &repo, number).await?;
+        }
+        (IssuesAction::Reopened, _) => {
+            let assignees: Vec<String> = reviewers(config.review_mode, &event.issue)
+                .iter()
+                .map(|a| a.login.clone())
+                .collect();
+            let db = ctx.db.get().await;
+            review_assignments::record_reopened(&db, &repo, number, &assignees).await?;
+        }
+        _ => {}
+    }
+    Ok(())
 }
 
 /// Returns a list of candidate usernames to choose as a reviewer.
@@ -679,6 +1423,7 @@ fn candidate_reviewers_from_names<'a>(
     config: &'a AssignConfig,
     issue: &Issue,
     names: &'a [String],
+    explanation: &mut Explanation,
 ) -> Result<HashSet<&'a str>, FindReviewerError> {
     // Set of candidate usernames to choose from. This uses a set to
     // deduplicate entries so that someone in multiple teams isn't
@@ -693,21 +1438,29 @@ fn candidate_reviewers_from_names<'a>(
     let mut group_expansion: Vec<&str> = names.iter().map(|n| n.as_str()).collect();
     // Keep track of which users get filtered out for a better error message.
     let mut filtered = Vec::new();
+    let mut filter_reasons = Vec::new();
     let repo = issue.repository();
     let org_prefix = format!("{}/", repo.organization);
     // Don't allow groups or teams to include the current author or assignee.
     let mut filter = |name: &&str| -> bool {
         let name_lower = name.to_lowercase();
-        let ok = name_lower != issue.user.login.to_lowercase()
-            && !config.is_on_vacation(name)
-            && !issue
-                .assignees
-                .iter()
-                .any(|assignee| name_lower == assignee.login.to_lowercase());
-        if !ok {
-            filtered.push(name.to_string());
-        }
-        ok
+        let reason = if name_lower == issue.user.login.to_lowercase() {
+            "the PR author"
+        } else if config.is_on_vacation(name) {
+            "on vacation"
+        } else if issue
+            .assignees
+            .iter()
+            .chain(&issue.requested_reviewers)
+            .any(|assignee| name_lower == assignee.login.to_lowercase())
+        {
+            "already assigned"
+        } else {
+            return true;
+        };
+        filtered.push(name.to_string());
+        filter_reasons.push(format!("`{name}` is excluded for being {reason}"));
+        false
     };
 
     // Loop over groups to recursively expand them.
@@ -722,6 +1475,12 @@ fn candidate_reviewers_from_names<'a>(
         if let Some(group_members) = config.adhoc_groups.get(maybe_group) {
             // If a group has already been expanded, don't expand it again.
             if seen.insert(maybe_group) {
+                explanation.note(|| {
+                    format!(
+                        "Group `{maybe_group}` expands to {}",
+                        code_list(group_members)
+                    )
+                });
                 group_expansion.extend(
                     group_members
                         .iter()
@@ -742,6 +1501,10 @@ fn candidate_reviewers_from_names<'a>(
             .strip_prefix("rust-lang/")
             .unwrap_or(group_or_user);
         if let Some(team) = teams.teams.get(maybe_team) {
+            explanation.note(|| {
+                let members: Vec<_> = team.members.iter().map(|m| &m.github).collect();
+                format!("Team `{maybe_team}` expands to {}", code_list(&members))
+            });
             candidates.extend(
                 team.members
                     .iter()
@@ -760,6 +1523,11 @@ fn candidate_reviewers_from_names<'a>(
             candidates.insert(group_or_user);
         }
     }
+    filter_reasons.sort();
+    filter_reasons.dedup();
+    for reason in filter_reasons {
+        explanation.note(|| reason);
+    }
     if candidates.is_empty() {
         let initial = names.iter().cloned().collect();
         if filtered.is_empty() {
@@ -0,0 +1,133 @@
+//! Support for reading reviewers from a repository's CODEOWNERS file, when
+//! `assign.codeowners` is enabled.
+//!
+//! See <https://docs.github.com/en/repositories/managing-your-repositorys-settings-and-features/customizing-your-repository/about-code-owners>
+//! for the format. Unlike `assign.owners`, where the longest matching path
+//! wins, the last matching line of a CODEOWNERS file wins.
+
+use crate::config::AssignConfig;
+use crate::github::{GithubClient, IssueRepository};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::fmt;
+use tracing as log;
+
+/// Where GitHub looks for a CODEOWNERS file, in order.
+const PATHS: &[&str] = &[".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(crate) struct Rule {
+    /// The line of the rule in the CODEOWNERS file, starting at 1.
+    pub(crate) line: usize,
+    pub(crate) pattern: String,
+    /// The owners, as names understood by `assign.owners`.
+    pub(crate) owners: Vec<String>,
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` (CODEOWNERS line {})", self.pattern, self.line)
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct CodeOwners {
+    rules: Vec<(Rule, Gitignore)>,
+}
+
+impl CodeOwners {
+    /// Parses a CODEOWNERS file of a repository in the `org` organization.
+    ///
+    /// Invalid lines are skipped, like GitHub does.
+    pub(crate) fn parse(contents: &str, org: &str, config: &AssignConfig) -> CodeOwners {
+        let mut rules = Vec::new();
+        for (i, line) in contents.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            // Comments may also follow the owners.
+            let line = line.split(" #").next().unwrap();
+            let mut fields = line.split_whitespace();
+            let pattern = fields.next().unwrap().replace("\\#", "#");
+            let matcher = match GitignoreBuilder::new("/")
+                .add_line(None, &pattern)
+                .and_then(|builder| builder.build())
+            {
+                Ok(matcher) => matcher,
+                Err(e) => {
+                    log::warn!("skipping invalid CODEOWNERS pattern `{pattern}`: {e}");
+                    continue;
+                }
+            };
+            let owners = fields
+                .filter_map(|owner| owner_name(owner, org, config))
+                .collect();
+            rules.push((
+                Rule {
+                    line: line_number,
+                    pattern,
+                    owners,
+                },
+                matcher,
+            ));
+        }
+        CodeOwners { rules }
+    }
+
+    /// Returns the rule that applies to the given path, if it has any owners.
+    pub(crate) fn rule_for(&self, path: &str) -> Option<&Rule> {
+        let (rule, _) =
+            self.rules.iter().rev().find(|(_, matcher)| {
+                matcher.matched_path_or_any_parents(path, false).is_ignore()
+            })?;
+        if rule.owners.is_empty() {
+            None
+        } else {
+            Some(rule)
+        }
+    }
+}
+
+/// Converts a CODEOWNERS owner to a name understood by `assign.owners`.
+///
+/// A `@org/team` of the repository's organization refers to the ad-hoc
+/// group of that name if there is one, and otherwise to the rust-lang team.
+/// Email addresses and teams of other organizations can't be mapped to
+/// reviewers, so they are ignored.
+fn owner_name(owner: &str, org: &str, config: &AssignConfig) -> Option<String> {
+    let name = owner.strip_prefix('@')?;
+    let Some((team_org, team)) = name.split_once('/') else {
+        return Some(name.to_string());
+    };
+    if !team_org.eq_ignore_ascii_case(org) && team_org != "rust-lang" {
+        log::debug!("ignoring CODEOWNERS owner `{owner}` from another organization");
+        return None;
+    }
+    if config.adhoc_groups.contains_key(team) {
+        Some(team.to_string())
+    } else {
+        Some(format!("rust-lang/{team}"))
+    }
+}
+
+/// Fetches and parses the CODEOWNERS file on the default branch of the
+/// repository, if it has one.
+pub(crate) async fn fetch(
+    gh: &GithubClient,
+    repo: &IssueRepository,
+    config: &AssignConfig,
+) -> anyhow::Result<Option<CodeOwners>> {
+    let full_name = repo.to_string();
+    for path in PATHS {
+        if let Some(contents) = gh.raw_file(&full_name, "HEAD", path).await? {
+            let contents = String::from_utf8_lossy(&contents);
+            return Ok(Some(CodeOwners::parse(
+                &contents,
+                &repo.organization,
+                config,
+            )));
+        }
+    }
+    Ok(None)
+}
@@ -13,7 +13,13 @@ fn test_from_names(
     let (teams, config, issue) = convert_simplified(teams, config, issue);
     let names: Vec<_> = names.iter().map(|n| n.to_string()).collect();
     match (
-        candidate_reviewers_from_names(&teams, &config, &issue, &names),
+        candidate_reviewers_from_names(
+            &teams,
+            &config,
+            &issue,
+            &names,
+            &mut Explanation::disabled(),
+        ),
         expected,
     ) {
         (Ok(candidates), Ok(expected)) => {
@@ -273,7 +279,7 @@ fn vacation() {
     let issue = generic_issue("octocat", "rust-lang/rust");
 
     // Test that `r? user` falls through to assigning from the team.
-    // See `determine_assignee` - ideally we would test that function directly instead of indirectly through `find_reviewer_from_names`.
+    // See `determine_assignee` - ideally we would test that function directly instead of indirectly through `find_reviewers_from_names`.
     let err_names = vec!["jyn514".into()];
     test_from_names(
         Some(teams.clone()),
@@ -295,3 +301,27 @@ fn vacation() {
         Ok(&["Mark-Simulacrum"]),
     );
 }
+
+#[test]
+fn explain() {
+    let teams = toml::toml!(compiler = ["octocat", "jyn514", "nikomatsakis"]);
+    let config = toml::toml!(
+        users_on_vacation = ["jyn514"]
+        [adhoc_groups]
+        reviewers = ["compiler", "@Mark-Simulacrum"]
+    );
+    let issue = generic_issue("octocat", "rust-lang/rust");
+    let (teams, config, issue) = convert_simplified(Some(teams), config, issue);
+    let mut explanation = Explanation::enabled();
+    let names = vec!["reviewers".to_string()];
+    candidate_reviewers_from_names(&teams, &config, &issue, &names, &mut explanation).unwrap();
+    assert_eq!(
+        explanation.lines(),
+        [
+            "Group `reviewers` expands to `compiler`, `@Mark-Simulacrum`",
+            "Team `compiler` expands to `octocat`, `jyn514`, `nikomatsakis`",
+            "`jyn514` is excluded for being on vacation",
+            "`octocat` is excluded for being the PR author",
+        ]
+    );
+}
@@ -0,0 +1,154 @@
+//! Tests for reading reviewers from CODEOWNERS
+
+use super::super::*;
+use super::tests_from_diff::make_fake_diff;
+use crate::config::AssignConfig;
+
+const CODEOWNERS: &str = "\
+# Default owners
+*           @rust-lang/libs
+/compiler/  @rust-lang/compiler @octocat   # inline comment
+/compiler/rustc_parse/ @parser-person someone@example.com
+*.md        @rust-lang/docs
+/compiler/rustc_parse/README.md
+/src/ @other-org/team @rust-lang/release
+";
+
+fn config(config: toml::Value) -> AssignConfig {
+    config.try_into().unwrap()
+}
+
+fn owners<'a>(codeowners: &'a CodeOwners, path: &str) -> Option<&'a [String]> {
+    codeowners.rule_for(path).map(|rule| rule.owners.as_slice())
+}
+
+#[test]
+fn parse() {
+    let codeowners = CodeOwners::parse(
+        CODEOWNERS,
+        "rust-lang",
+        &config(toml::Value::Table(Default::default())),
+    );
+    assert_eq!(
+        owners(&codeowners, "library/core/src/lib.rs"),
+        Some(&["rust-lang/libs".to_string()][..])
+    );
+    assert_eq!(
+        owners(&codeowners, "compiler/rustc_middle/src/lib.rs"),
+        Some(&["rust-lang/compiler".to_string(), "octocat".to_string()][..])
+    );
+    // Emails are ignored.
+    assert_eq!(
+        owners(&codeowners, "compiler/rustc_parse/src/lib.rs"),
+        Some(&["parser-person".to_string()][..])
+    );
+    // Teams of other organizations are ignored.
+    assert_eq!(
+        owners(&codeowners, "src/tools/x.rs"),
+        Some(&["rust-lang/release".to_string()][..])
+    );
+}
+
+#[test]
+fn last_match_wins() {
+    let codeowners = CodeOwners::parse(
+        CODEOWNERS,
+        "rust-lang",
+        &config(toml::Value::Table(Default::default())),
+    );
+    let rule = codeowners.rule_for("compiler/README.md").unwrap();
+    assert_eq!(rule.pattern, "*.md");
+    assert_eq!(rule.line, 5);
+    // The last matching rule has no owners, which leaves the file unowned.
+    assert_eq!(owners(&codeowners, "compiler/rustc_parse/README.md"), None);
+}
+
+#[test]
+fn org_teams() {
+    let aconfig = config(toml::toml!(
+        [adhoc_groups]
+        compiler = ["@octocat"]
+    ));
+    let codeowners = CodeOwners::parse(
+        "/compiler/ @my-org/compiler\n/library/ @my-org/libs @rust-lang/libs-api\n",
+        "my-org",
+        &aconfig,
+    );
+    assert_eq!(
+        owners(&codeowners, "compiler/lib.rs"),
+        Some(&["compiler".to_string()][..])
+    );
+    assert_eq!(
+        owners(&codeowners, "library/lib.rs"),
+        Some(
+            &[
+                "rust-lang/libs".to_string(),
+                "rust-lang/libs-api".to_string()
+            ][..]
+        )
+    );
+}
+
+#[test]
+fn owners_take_precedence() {
+    let aconfig = config(toml::toml!(
+        codeowners = true
+        [owners]
+        "/compiler/rustc_parse" = ["parser"]
+    ));
+    let codeowners = CodeOwners::parse(CODEOWNERS, "rust-lang", &aconfig);
+    let diff = make_fake_diff(&[("compiler/rustc_parse/src/lib.rs", 5, 0)]);
+    assert_eq!(
+        find_reviewers_from_diff(
+            &aconfig,
+            Some(&codeowners),
+            &diff,
+            &mut Explanation::disabled()
+        )
+        .unwrap(),
+        (vec!["parser".to_string()], 1)
+    );
+    let diff = make_fake_diff(&[
+        ("compiler/rustc_parse/src/lib.rs", 1, 0),
+        ("compiler/rustc_middle/src/lib.rs", 5, 5),
+    ]);
+    let mut explanation = Explanation::enabled();
+    assert_eq!(
+        find_reviewers_from_diff(&aconfig, Some(&codeowners), &diff, &mut explanation).unwrap(),
+        (
+            vec!["octocat".to_string(), "rust-lang/compiler".to_string()],
+            1
+        )
+    );
+    assert_eq!(
+        explanation.lines()[1],
+        "`compiler/rustc_middle/src/lib.rs` matches `/compiler/` (CODEOWNERS line 3)"
+    );
+}
+
+#[test]
+fn other_org_teams() {
+    let aconfig = config(toml::toml!(codeowners = true));
+    let codeowners = CodeOwners::parse(
+        "/compiler/ @other-org/compiler\n/library/ @other-org/libs @octocat\n",
+        "rust-lang",
+        &aconfig,
+    );
+    // A rule with only teams of other organizations leaves the files unowned.
+    assert_eq!(owners(&codeowners, "compiler/lib.rs"), None);
+    assert_eq!(
+        owners(&codeowners, "library/lib.rs"),
+        Some(&["octocat".to_string()][..])
+    );
+    let diff = make_fake_diff(&[("library/lib.rs", 1, 0)]);
+    assert_eq!(
+        find_reviewers_from_diff(
+            &aconfig,
+            Some(&codeowners),
+            &diff,
+            &mut Explanation::disabled()
+        )
+        .unwrap(),
+        (vec!["octocat".to_string()], 1)
+    );
+}
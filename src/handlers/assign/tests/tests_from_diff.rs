@@ -7,7 +7,9 @@ use std::fmt::Write;
 fn test_from_diff(diff: &str, config: toml::Value, expected: &[&str]) {
     let aconfig: AssignConfig = config.try_into().unwrap();
     assert_eq!(
-        find_reviewers_from_diff(&aconfig, diff).unwrap(),
+        find_reviewers_from_diff(&aconfig, None, diff, &mut Explanation::disabled())
+            .unwrap()
+            .0,
         expected.iter().map(|x| x.to_string()).collect::<Vec<_>>()
     );
 }
@@ -17,7 +19,7 @@ fn test_from_diff(diff: &str, config: toml::Value, expected: &[&str]) {
 /// `paths` should be a slice of `(path, added, removed)` tuples where `added`
 /// is the number of lines added, and `removed` is the number of lines
 /// removed.
-fn make_fake_diff(paths: &[(&str, u32, u32)]) -> String {
+pub(super) fn make_fake_diff(paths: &[(&str, u32, u32)]) -> String {
     // This isn't a properly structured diff, but it has approximately enough
     // information for what is needed for testing.
     paths
@@ -146,3 +148,83 @@ fn basic_gitignore_pattern() {
     let diff = make_fake_diff(&[("src/librustdoc/html/static/js/settings.js", 10, 1)]);
     test_from_diff(&diff, config, &["javascript-reviewers"]);
 }
+
+#[test]
+fn min_reviewers() {
+    let config = toml::toml!(
+        [owners]
+        "/compiler" = ["compiler"]
+        "/library/core" = { reviewers = ["libs", "@octocat"], min_reviewers = 2 }
+    );
+    let aconfig: AssignConfig = config.try_into().unwrap();
+    let diff = make_fake_diff(&[("library/core/src/lib.rs", 5, 0)]);
+    assert_eq!(
+        find_reviewers_from_diff(&aconfig, None, &diff, &mut Explanation::disabled()).unwrap(),
+        (vec!["@octocat".to_string(), "libs".to_string()], 2)
+    );
+    let diff = make_fake_diff(&[("compiler/rustc_parse/src/lib.rs", 5, 0)]);
+    assert_eq!(
+        find_reviewers_from_diff(&aconfig, None, &diff, &mut Explanation::disabled()).unwrap(),
+        (vec!["compiler".to_string()], 1)
+    );
+}
+
+#[test]
+fn explain() {
+    let config = toml::toml!(
+        [owners]
+        "/compiler" = ["compiler"]
+        "/compiler/rustc_parse" = ["parser"]
+    );
+    let aconfig: AssignConfig = config.try_into().unwrap();
+    let diff = make_fake_diff(&[
+        ("compiler/rustc_parse/src/lib.rs", 5, 0),
+        ("compiler/rustc_middle/src/lib.rs", 1, 1),
+        ("README.md", 1, 0),
+    ]);
+    let mut explanation = Explanation::enabled();
+    find_reviewers_from_diff(&aconfig, None, &diff, &mut explanation).unwrap();
+    assert_eq!(
+        explanation.lines(),
+        [
+            "`compiler/rustc_parse/src/lib.rs` matches `/compiler/rustc_parse`",
+            "`compiler/rustc_middle/src/lib.rs` matches `/compiler`",
+            "`README.md` doesn't match any owners",
+            "Modified lines (plus one per file) by owners entry: `/compiler` (3), `/compiler/rustc_parse` (6)",
+            "Choosing 1 reviewer(s) from the most modified entries: `parser`",
+        ]
+    );
+}
+
+#[test]
+fn explain_many_files() {
+    let config = toml::toml!(
+        [owners]
+        "/compiler" = ["compiler"]
+    );
+    let aconfig: AssignConfig = config.try_into().unwrap();
+    let mut paths: Vec<_> = (0..1000)
+        .map(|n| format!("compiler/rustc_{n}/src/lib.rs"))
+        .collect();
+    paths.push("README.md".to_string());
+    paths.push("Cargo.toml".to_string());
+    let files: Vec<_> = paths.iter().map(|p| (p.as_str(), 1, 0)).collect();
+    let diff = make_fake_diff(&files);
+    let mut explanation = Explanation::enabled();
+    find_reviewers_from_diff(&aconfig, None, &diff, &mut explanation).unwrap();
+    assert_eq!(
+        explanation.lines(),
+        [
+            "`compiler/rustc_0/src/lib.rs` matches `/compiler`",
+            "`compiler/rustc_1/src/lib.rs` matches `/compiler`",
+            "`compiler/rustc_2/src/lib.rs` matches `/compiler`",
+            "`compiler/rustc_3/src/lib.rs` matches `/compiler`",
+            "`compiler/rustc_4/src/lib.rs` matches `/compiler`",
+            "995 more file(s) match `/compiler`",
+            "`README.md` doesn't match any owners",
+            "`Cargo.toml` doesn't match any owners",
+            "Modified lines (plus one per file) by owners entry: `/compiler` (2000)",
+            "Choosing 1 reviewer(s) from the most modified entries: `compiler`",
+        ]
+    );
+}
@@ -0,0 +1,178 @@
+//! Tests for `select_reviewer` and `select_reviewers`
+
+use super::super::*;
+use chrono::{TimeZone, Utc};
+
+fn load(open: i64, recent: i64, last_assigned_day: Option<u32>) -> ReviewerLoad {
+    ReviewerLoad {
+        open,
+        recent,
+        last_assigned_at: last_assigned_day.map(|day| Utc.ymd(2023, 1, day).and_hms(0, 0, 0)),
+    }
+}
+
+fn test_select(
+    candidates: &[&str],
+    selection: ReviewerSelection,
+    loads: &[(&str, ReviewerLoad)],
+    expected: &str,
+) {
+    let candidates: HashSet<&str> = candidates.iter().copied().collect();
+    let loads: HashMap<String, ReviewerLoad> = loads
+        .iter()
+        .map(|(name, load)| (name.to_string(), load.clone()))
+        .collect();
+    // Ties are broken randomly, so try a few times.
+    for _ in 0..10 {
+        assert_eq!(select_reviewer(&candidates, selection, &loads), expected);
+    }
+}
+
+#[test]
+fn random() {
+    let candidates: HashSet<&str> = ["alice", "bob"].into_iter().collect();
+    let selected = select_reviewer(&candidates, ReviewerSelection::Random, &HashMap::new());
+    assert!(candidates.contains(selected));
+}
+
+#[test]
+fn least_loaded_open() {
+    test_select(
+        &["alice", "bob", "carol"],
+        ReviewerSelection::LeastLoaded,
+        &[
+            ("alice", load(3, 5, Some(1))),
+            ("bob", load(1, 8, Some(2))),
+            ("carol", load(2, 0, None)),
+        ],
+        "bob",
+    );
+}
+
+#[test]
+fn least_loaded_recent() {
+    test_select(
+        &["alice", "bob"],
+        ReviewerSelection::LeastLoaded,
+        &[("alice", load(1, 5, Some(1))), ("bob", load(1, 2, Some(2)))],
+        "bob",
+    );
+}
+
+#[test]
+fn least_loaded_never_assigned() {
+    test_select(
+        &["alice", "bob"],
+        ReviewerSelection::LeastLoaded,
+        &[("alice", load(1, 1, Some(1)))],
+        "bob",
+    );
+}
+
+#[test]
+fn round_robin() {
+    test_select(
+        &["alice", "bob", "carol"],
+        ReviewerSelection::RoundRobin,
+        &[
+            ("alice", load(0, 1, Some(3))),
+            ("bob", load(4, 9, Some(1))),
+            ("carol", load(0, 1, Some(2))),
+        ],
+        "bob",
+    );
+}
+
+#[test]
+fn round_robin_never_assigned() {
+    test_select(
+        &["alice", "bob"],
+        ReviewerSelection::RoundRobin,
+        &[("alice", load(0, 1, Some(1)))],
+        "bob",
+    );
+}
+
+#[test]
+fn case_insensitive() {
+    test_select(
+        &["Alice", "Bob"],
+        ReviewerSelection::LeastLoaded,
+        &[("alice", load(0, 0, None)), ("bob", load(2, 2, Some(1)))],
+        "Alice",
+    );
+}
+
+#[test]
+fn unavailable() {
+    let today = chrono::NaiveDate::from_ymd(2023, 1, 10);
+    let away = ReviewPrefs {
+        away_until: Some(chrono::NaiveDate::from_ymd(2023, 1, 11)),
+        max_assigned_prs: None,
+    };
+    assert_eq!(
+        unavailable_reason(Some(&away), None, today).as_deref(),
+        Some("away until 2023-01-11")
+    );
+    let back = ReviewPrefs {
+        away_until: Some(today),
+        max_assigned_prs: None,
+    };
+    assert_eq!(unavailable_reason(Some(&back), None, today), None);
+    assert_eq!(
+        unavailable_reason(None, Some(&load(5, 5, None)), today),
+        None
+    );
+}
+
+#[test]
+fn capacity() {
+    let today = chrono::NaiveDate::from_ymd(2023, 1, 10);
+    let prefs = ReviewPrefs {
+        away_until: None,
+        max_assigned_prs: Some(2),
+    };
+    assert_eq!(unavailable_reason(Some(&prefs), None, today), None);
+    assert_eq!(
+        unavailable_reason(Some(&prefs), Some(&load(1, 5, None)), today),
+        None
+    );
+    assert_eq!(
+        unavailable_reason(Some(&prefs), Some(&load(2, 2, None)), today).as_deref(),
+        Some("at capacity with 2 assigned PRs")
+    );
+}
+
+#[test]
+fn several_distinct() {
+    let candidates: HashSet<&str> = ["alice", "bob", "carol"].into_iter().collect();
+    let loads: HashMap<String, ReviewerLoad> = [
+        ("alice".to_string(), load(3, 0, None)),
+        ("bob".to_string(), load(1, 0, None)),
+        ("carol".to_string(), load(2, 0, None)),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(
+        select_reviewers(
+            candidates.clone(),
+            2,
+            ReviewerSelection::LeastLoaded,
+            &loads
+        ),
+        ["bob", "carol"]
+    );
+    // There are only three candidates.
+    let mut selected = select_reviewers(candidates, 5, ReviewerSelection::Random, &loads);
+    selected.sort();
+    assert_eq!(selected, ["alice", "bob", "carol"]);
+}
+
+#[test]
+fn dedup_requested_names() {
+    let names: Vec<String> = ["alice", "compiler", "Alice", "bob", "ALICE"]
+        .iter()
+        .map(|n| n.to_string())
+        .collect();
+    assert_eq!(dedup_names(&names), ["alice", "compiler", "bob"]);
+}
@@ -1,6 +1,6 @@
 use crate::{
     config::AutolabelConfig,
-    github::{files_changed, IssuesAction, IssuesEvent, Label},
+    github::{files_changed, IssuesAction, IssuesEvent, Label, PullRequestData},
     handlers::Context,
 };
 use anyhow::Context as _;
@@ -14,6 +14,7 @@ pub(super) struct AutolabelInput {
 pub(super) async fn parse_input(
     ctx: &Context,
     event: &IssuesEvent,
+    pr: &PullRequestData<'_>,
     config: Option<&AutolabelConfig>,
 ) -> Result<Option<AutolabelInput>, String> {
     let config = match config {
@@ -27,15 +28,14 @@ pub(super) async fn parse_input(
     // remove. Not much can be done about that currently; the before/after on
     // synchronize may be straddling a rebase, which will break diff generation.
     if event.action == IssuesAction::Opened || event.action == IssuesAction::Synchronize {
-        let diff = event
-            .issue
+        let diff = pr
             .diff(&ctx.github)
             .await
             .map_err(|e| {
                 log::error!("failed to fetch diff: {:?}", e);
             })
             .unwrap_or_default();
-        let files = diff.as_deref().map(files_changed);
+        let files = diff.map(files_changed);
         let mut autolabels = Vec::new();
 
         'outer: for (label, cfg) in config.labels.iter() {
@@ -2,8 +2,11 @@
 
 use crate::db::jobs::JobSchedule;
 use crate::github::{self, GitTreeEntry, GithubClient, Issue, Repository};
-use anyhow::Context;
+use crate::handlers::Context;
+use crate::jobs::JobHandler;
+use anyhow::Context as _;
 use anyhow::Result;
+use async_trait::async_trait;
 use cron::Schedule;
 use reqwest::Client;
 use std::fmt::Write;
@@ -30,13 +33,25 @@ const TITLE: &str = "Update books";
 
 pub fn job() -> JobSchedule {
     JobSchedule {
-        name: "docs_update".to_string(),
+        name: DocsUpdateJob::NAME.to_string(),
         // Around 9am Pacific time on every Monday.
         schedule: Schedule::from_str("0 00 17 * * Mon *").unwrap(),
         metadata: serde_json::Value::Null,
     }
 }
 
+pub struct DocsUpdateJob;
+
+#[async_trait]
+impl JobHandler for DocsUpdateJob {
+    const NAME: &'static str = "docs_update";
+    type Metadata = ();
+
+    async fn run(&self, _ctx: &Context, _metadata: ()) -> Result<()> {
+        handle_job().await
+    }
+}
+
 pub async fn handle_job() -> Result<()> {
     // Only run every other week. Doing it every week can be a bit noisy, and
     // (rarely) a PR can take longer than a week to merge (like if there are
@@ -1,32 +1,27 @@
-// Function to match the scheduled job function with its corresponding handler.
-// In case you want to add a new one, just add a new clause to the match with
-// the job name and the corresponding function.
+// The handlers of all the jobs. In case you want to add a new one, just add
+// its handler to the registry.
 
 // Further info could be find in src/jobs.rs
 
 use super::Context;
+use crate::jobs::{handler, DynJobHandler};
+
+pub fn registry() -> Vec<Box<dyn DynJobHandler>> {
+    vec![
+        Box::new(super::docs_update::DocsUpdateJob),
+        Box::new(super::major_change::MajorChangeAcceptJob),
+        Box::new(super::rustc_commits::RustcCommitsJob),
+        Box::new(super::stale_reviews::StaleReviewsJob),
+    ]
+}
 
 pub async fn handle_job(
     ctx: &Context,
-    name: &String,
+    name: &str,
     metadata: &serde_json::Value,
 ) -> anyhow::Result<()> {
-    match name.as_str() {
-        "docs_update" => super::docs_update::handle_job().await,
-        "rustc_commits" => {
-            super::rustc_commits::synchronize_commits_inner(ctx, None).await;
-            Ok(())
-        }
-        _ => default(&name, &metadata),
+    match handler(name) {
+        Some(handler) => handler.run(ctx, metadata).await,
+        None => anyhow::bail!("no handler for job {name}"),
     }
 }
-
-fn default(name: &String, metadata: &serde_json::Value) -> anyhow::Result<()> {
-    tracing::trace!(
-        "handle_job fell into default case: (name={:?}, metadata={:?})",
-        name,
-        metadata
-    );
-
-    Ok(())
-}
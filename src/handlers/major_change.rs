@@ -1,23 +1,148 @@
 use crate::{
-    config::MajorChangeConfig,
-    github::{Event, Issue, IssuesAction, IssuesEvent, Label, ZulipGitHubReference},
+    config::{self, MajorChangeConfig},
+    db::issue_data::IssueData,
+    github::{
+        Event, Issue, IssuesAction, IssuesEvent, Label, PullRequestData, ZulipGitHubReference,
+    },
     handlers::Context,
-    interactions::ErrorComment,
+    interactions::{EditIssueBody, ErrorComment},
+    jobs::{schedule_job, JobHandler},
 };
 use anyhow::Context as _;
-use parser::command::second::SecondCommand;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use parser::command::{concern::ConcernCommand, second::SecondCommand};
+use serde::{Deserialize, Serialize};
 use tracing as log;
 
+const MAJOR_CHANGE_KEY: &str = "major_change";
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct MajorChangeState {
+    /// When the proposal will be accepted, if it has been seconded. This
+    /// identifies the pending [`MajorChangeAcceptJob`], so that it is
+    /// cancelled when the proposal is closed or seconded again.
+    accept_at: Option<DateTime<Utc>>,
+    /// The `accept_at` of an acceptance by [`MajorChangeAcceptJob`] which
+    /// hasn't been announced on the issue yet. A retry of the job uses it to
+    /// finish the acceptance even though the labels are already set.
+    #[serde(default)]
+    accepting: Option<DateTime<Utc>>,
+    /// The concerns raised on the proposal, in the order they were raised.
+    #[serde(default)]
+    concerns: Vec<Concern>,
+}
+
+impl MajorChangeState {
+    fn has_open_concerns(&self) -> bool {
+        self.concerns.iter().any(|c| c.resolved_by.is_none())
+    }
+
+    /// Raises a concern, or raises a resolved concern again. Returns the
+    /// message to show if a concern with that name is already open.
+    fn raise_concern(&mut self, name: &str, author: &str, comment_url: &str) -> Result<(), String> {
+        match self.concerns.iter_mut().find(|c| c.name == name) {
+            Some(concern) if concern.resolved_by.is_none() => {
+                return Err(format!("A concern named `{name}` is already open."));
+            }
+            Some(concern) => {
+                concern.author = author.to_string();
+                concern.comment_url = comment_url.to_string();
+                concern.resolved_by = None;
+            }
+            None => self.concerns.push(Concern {
+                name: name.to_string(),
+                author: author.to_string(),
+                comment_url: comment_url.to_string(),
+                resolved_by: None,
+            }),
+        }
+        Ok(())
+    }
+
+    /// Resolves an open concern. Returns the message to show if there is no
+    /// open concern with that name.
+    fn resolve_concern(&mut self, name: &str, user: &str) -> Result<(), String> {
+        let Some(concern) = self
+            .concerns
+            .iter_mut()
+            .find(|c| c.name == name && c.resolved_by.is_none())
+        else {
+            return Err(format!("There is no open concern named `{name}`."));
+        };
+        concern.resolved_by = Some(user.to_string());
+        Ok(())
+    }
+
+    /// Returns the `accept_at` of an acceptance which is due but was blocked
+    /// by concerns that have all been resolved since.
+    fn overdue_acceptance(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.accept_at
+            .filter(|accept_at| *accept_at <= now && !self.has_open_concerns())
+    }
+
+    /// Renders the concerns for the issue body.
+    fn concerns_markdown(&self) -> String {
+        let mut text = String::from("\n### Concerns\n");
+        for concern in &self.concerns {
+            match &concern.resolved_by {
+                None => text.push_str(&format!(
+                    "\n- {} (raised by @{} [here]({}))",
+                    concern.name, concern.author, concern.comment_url
+                )),
+                Some(resolved_by) => text.push_str(&format!(
+                    "\n- ~~{}~~ resolved by @{} (raised by @{} [here]({}))",
+                    concern.name, resolved_by, concern.author, concern.comment_url
+                )),
+            }
+        }
+        text.push_str(
+            "\n\nThe proposal won't be accepted while a concern is open. \
+            Use `@rustbot concern <name>` to raise a concern, and `@rustbot resolve <name>` \
+            to resolve it.",
+        );
+        text
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Concern {
+    name: String,
+    author: String,
+    comment_url: String,
+    resolved_by: Option<String>,
+}
+
+/// The commands handled by [`handle_command`].
+pub(super) enum MajorChangeCommand {
+    Second(SecondCommand),
+    Concern(ConcernCommand),
+}
+
+impl From<SecondCommand> for MajorChangeCommand {
+    fn from(cmd: SecondCommand) -> Self {
+        MajorChangeCommand::Second(cmd)
+    }
+}
+
+impl From<ConcernCommand> for MajorChangeCommand {
+    fn from(cmd: ConcernCommand) -> Self {
+        MajorChangeCommand::Concern(cmd)
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub enum Invocation {
     NewProposal,
     AcceptedProposal,
     Rename { prev_issue: ZulipGitHubReference },
+    Closed,
 }
 
 pub(super) async fn parse_input(
     _ctx: &Context,
     event: &IssuesEvent,
+    _pr: &PullRequestData<'_>,
     config: Option<&MajorChangeConfig>,
 ) -> Result<Option<Invocation>, String> {
     let config = if let Some(config) = config {
@@ -54,7 +179,8 @@ pub(super) async fn parse_input(
         }
     }
 
-    // If we were labeled with accepted, then issue that event
+    // If we were labeled with accepted, then issue that event. This is
+    // also how the acceptance by `MajorChangeAcceptJob` is announced.
     if event.action == IssuesAction::Labeled
         && event
             .label
@@ -64,6 +190,16 @@ pub(super) async fn parse_input(
         return Ok(Some(Invocation::AcceptedProposal));
     }
 
+    if event.action == IssuesAction::Closed
+        && event
+            .issue
+            .labels()
+            .iter()
+            .any(|l| l.name == enabling_label)
+    {
+        return Ok(Some(Invocation::Closed));
+    }
+
     // Opening an issue with a label assigned triggers both
     // "Opened" and "Labeled" events.
     //
@@ -181,6 +317,7 @@ pub(super) async fn handle_input(
 
             return Ok(());
         }
+        Invocation::Closed => return cancel_acceptance(ctx, &event.issue).await,
     };
     handle(
         ctx,
@@ -197,21 +334,22 @@ pub(super) async fn handle_command(
     ctx: &Context,
     config: &MajorChangeConfig,
     event: &Event,
-    _cmd: SecondCommand,
+    cmd: impl Into<MajorChangeCommand>,
 ) -> anyhow::Result<()> {
     let issue = event.issue().unwrap();
-
+    let cmd = cmd.into();
     if !issue
         .labels()
         .iter()
         .any(|l| l.name == config.enabling_label)
     {
+        let message = match cmd {
+            MajorChangeCommand::Second(_) => "This issue cannot be seconded",
+            MajorChangeCommand::Concern(_) => "Concerns cannot be raised or resolved on this issue",
+        };
         let cmnt = ErrorComment::new(
             &issue,
-            &format!(
-                "This issue cannot be seconded; it lacks the `{}` label.",
-                config.enabling_label
-            ),
+            &format!("{message}; it lacks the `{}` label.", config.enabling_label),
         );
         cmnt.post(&ctx.github).await?;
         return Ok(());
@@ -225,16 +363,33 @@ pub(super) async fn handle_command(
         .unwrap_or(false);
 
     if !is_team_member {
-        let cmnt = ErrorComment::new(&issue, "Only team members can second issues.");
+        let message = match cmd {
+            MajorChangeCommand::Second(_) => "Only team members can second issues.",
+            MajorChangeCommand::Concern(_) => "Only team members can raise or resolve concerns.",
+        };
+        let cmnt = ErrorComment::new(&issue, message);
         cmnt.post(&ctx.github).await?;
         return Ok(());
     }
 
+    match cmd {
+        MajorChangeCommand::Second(_) => handle_second(ctx, config, event, issue).await,
+        MajorChangeCommand::Concern(cmd) => handle_concern(ctx, config, event, issue, cmd).await,
+    }
+}
+
+async fn handle_second(
+    ctx: &Context,
+    config: &MajorChangeConfig,
+    event: &Event,
+    issue: &Issue,
+) -> anyhow::Result<()> {
     let zulip_msg = format!(
-        "@*{}*: Proposal [#{}]({}) has been seconded, and will be approved in 10 days if no objections are raised.",
+        "@*{}*: Proposal [#{}]({}) has been seconded, and will be approved in {} days if no objections are raised.",
         config.zulip_ping,
         issue.number,
-        event.html_url().unwrap()
+        event.html_url().unwrap(),
+        config.waiting_period_days,
     );
 
     handle(
@@ -245,9 +400,244 @@ pub(super) async fn handle_command(
         config.second_label.clone(),
         false,
     )
+    .await?;
+
+    let accept_at = Utc::now() + Duration::days(i64::from(config.waiting_period_days));
+    let mut client = ctx.db.get().await;
+    let mut state: IssueData<'_, MajorChangeState> =
+        IssueData::load(&mut client, issue, MAJOR_CHANGE_KEY).await?;
+    state.data.accept_at = Some(accept_at);
+    state.save().await?;
+    schedule_job::<MajorChangeAcceptJob>(
+        &client,
+        accept_at,
+        &AcceptMetadata {
+            repo: issue.repository().to_string(),
+            issue_number: issue.number,
+            accept_at,
+        },
+    )
     .await
 }
 
+async fn handle_concern(
+    ctx: &Context,
+    config: &MajorChangeConfig,
+    event: &Event,
+    issue: &Issue,
+    cmd: ConcernCommand,
+) -> anyhow::Result<()> {
+    let user = &event.user().login;
+    let comment_url = event.html_url().unwrap();
+
+    // The state is saved before the issue body and Zulip are updated, so that
+    // the `issue_data` table isn't locked during the requests.
+    let result = {
+        let mut client = ctx.db.get().await;
+        let mut state: IssueData<'_, MajorChangeState> =
+            IssueData::load(&mut client, issue, MAJOR_CHANGE_KEY).await?;
+        let result = match &cmd {
+            ConcernCommand::Concern { name } => state.data.raise_concern(name, user, comment_url),
+            ConcernCommand::Resolve { name } => state.data.resolve_concern(name, user),
+        };
+        match result {
+            Ok(()) => {
+                let markdown = state.data.concerns_markdown();
+                let all_resolved = !state.data.has_open_concerns();
+                let overdue = state.data.overdue_acceptance(Utc::now());
+                state.save().await?;
+                // The waiting period ended while the concerns were open.
+                if let Some(accept_at) = overdue {
+                    schedule_job::<MajorChangeAcceptJob>(
+                        &client,
+                        Utc::now(),
+                        &AcceptMetadata {
+                            repo: issue.repository().to_string(),
+                            issue_number: issue.number,
+                            accept_at,
+                        },
+                    )
+                    .await?;
+                }
+                Ok((markdown, all_resolved))
+            }
+            Err(message) => Err(message),
+        }
+    };
+    let (markdown, all_resolved) = match result {
+        Ok(result) => result,
+        Err(message) => return ErrorComment::new(issue, message).post(&ctx.github).await,
+    };
+
+    let zulip_msg = match cmd {
+        ConcernCommand::Concern { name } => format!(
+            "@{user} has raised a [concern]({comment_url}) `{name}` on proposal [#{}]({}), \
+            which won't be accepted until it is resolved.",
+            issue.number, issue.html_url
+        ),
+        ConcernCommand::Resolve { name } => {
+            let mut msg = format!(
+                "@{user} has [resolved]({comment_url}) the concern `{name}` on proposal [#{}]({}).",
+                issue.number, issue.html_url
+            );
+            if all_resolved {
+                msg.push_str(" All the concerns have been resolved.");
+            }
+            msg
+        }
+    };
+    EditIssueBody::new(issue, "CONCERNS")
+        .apply(&ctx.github, markdown, ())
+        .await?;
+    post_zulip(ctx, config, issue, &zulip_msg).await
+}
+
+/// Cancels the pending acceptance of a proposal, if any.
+///
+/// Reopening the proposal doesn't reschedule it, so the issue is told that
+/// it needs to be seconded again.
+async fn cancel_acceptance(ctx: &Context, issue: &Issue) -> anyhow::Result<()> {
+    let cancelled = {
+        let mut client = ctx.db.get().await;
+        let mut state: IssueData<'_, MajorChangeState> =
+            IssueData::load(&mut client, issue, MAJOR_CHANGE_KEY).await?;
+        let cancelled = state.data.accept_at.take().is_some();
+        if cancelled {
+            log::info!("cancelled the acceptance of {}", issue.global_id());
+            state.save().await?;
+        }
+        cancelled
+    };
+    if cancelled {
+        issue
+            .post_comment(
+                &ctx.github,
+                &format!(
+                    "The pending acceptance of this proposal was cancelled, since it was \
+                     closed. If it is reopened, it needs to be seconded again with \
+                     `@{} second` to be accepted.",
+                    ctx.username
+                ),
+            )
+            .await?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AcceptMetadata {
+    /// The full name of the repository.
+    repo: String,
+    issue_number: u64,
+    /// The `accept_at` of the [`MajorChangeState`] when the job was
+    /// scheduled.
+    accept_at: DateTime<Utc>,
+}
+
+/// Accepts a proposal at the end of its waiting period, unless it has been
+/// closed or its acceptance was rescheduled since the job was scheduled.
+///
+/// A proposal with open concerns isn't accepted. It is accepted when the last
+/// concern is resolved instead.
+pub struct MajorChangeAcceptJob;
+
+#[async_trait]
+impl JobHandler for MajorChangeAcceptJob {
+    const NAME: &'static str = "major_change_accept";
+    type Metadata = AcceptMetadata;
+
+    async fn run(&self, ctx: &Context, metadata: AcceptMetadata) -> anyhow::Result<()> {
+        let repo = ctx.github.repository(&metadata.repo).await?;
+        let Some(config) = &config::get(&ctx.github, &repo).await?.major_change else {
+            return Ok(());
+        };
+        let issue = repo.get_issue(&ctx.github, metadata.issue_number).await?;
+
+        // The decision is saved before GitHub is touched, so that the
+        // `issue_data` table isn't locked during the requests.
+        {
+            let mut client = ctx.db.get().await;
+            let mut state: IssueData<'_, MajorChangeState> =
+                IssueData::load(&mut client, &issue, MAJOR_CHANGE_KEY).await?;
+            if state.data.accepting != Some(metadata.accept_at) {
+                if state.data.accept_at != Some(metadata.accept_at) {
+                    log::info!(
+                        "not accepting {}, its acceptance was cancelled or rescheduled",
+                        issue.global_id()
+                    );
+                    return Ok(());
+                }
+                if state.data.has_open_concerns() {
+                    log::info!("not accepting {}, it has open concerns", issue.global_id());
+                    return Ok(());
+                }
+                let labels = issue.labels();
+                // The labels may have been changed manually in the meantime.
+                let accept = issue.is_open()
+                    && labels.iter().any(|l| l.name == config.second_label)
+                    && !labels.iter().any(|l| l.name == config.accept_label);
+                state.data.accept_at = None;
+                if accept {
+                    state.data.accepting = Some(metadata.accept_at);
+                }
+                state.save().await?;
+                if !accept {
+                    return Ok(());
+                }
+            }
+        }
+
+        accept(ctx, config, &issue).await?;
+
+        let mut client = ctx.db.get().await;
+        let mut state: IssueData<'_, MajorChangeState> =
+            IssueData::load(&mut client, &issue, MAJOR_CHANGE_KEY).await?;
+        if state.data.accepting == Some(metadata.accept_at) {
+            state.data.accepting = None;
+            state.save().await?;
+        }
+        Ok(())
+    }
+}
+
+/// Sets the labels of an accepted proposal and comments on it.
+///
+/// Adding the `accept_label` triggers the [`Invocation::AcceptedProposal`]
+/// announcement on Zulip. Labels set by a previous attempt are left alone, so
+/// that a retry only posts what is missing.
+async fn accept(ctx: &Context, config: &MajorChangeConfig, issue: &Issue) -> anyhow::Result<()> {
+    let labels = issue.labels();
+    if !labels.iter().any(|l| l.name == config.accept_label) {
+        issue
+            .add_labels(
+                &ctx.github,
+                vec![Label {
+                    name: config.accept_label.clone(),
+                }],
+            )
+            .await
+            .context("label setting failed")?;
+    }
+    if labels.iter().any(|l| l.name == config.second_label) {
+        issue
+            .remove_label(&ctx.github, &config.second_label)
+            .await
+            .context("label removal failed")?;
+    }
+
+    issue
+        .post_comment(
+            &ctx.github,
+            &format!(
+                "The {}-day waiting period has ended without objections, \
+                so this proposal has been accepted.",
+                config.waiting_period_days
+            ),
+        )
+        .await
+        .context("post major change comment")
+}
+
 async fn handle(
     ctx: &Context,
     config: &MajorChangeConfig,
@@ -296,6 +686,27 @@ async fn handle(
     Ok(())
 }
 
+/// Posts a message in the Zulip topic of the proposal.
+async fn post_zulip(
+    ctx: &Context,
+    config: &MajorChangeConfig,
+    issue: &Issue,
+    content: &str,
+) -> anyhow::Result<()> {
+    let zulip_topic = zulip_topic_from_issue(&issue.to_zulip_github_reference());
+    crate::zulip::MessageApiRequest {
+        recipient: crate::zulip::Recipient::Stream {
+            id: config.zulip_stream,
+            topic: &zulip_topic,
+        },
+        content,
+    }
+    .send(ctx.github.raw())
+    .await
+    .context("zulip post failed")?;
+    Ok(())
+}
+
 fn zulip_topic_from_issue(issue: &ZulipGitHubReference) -> String {
     // Concatenate the issue title and the topic reference, truncating such that
     // the overall length does not exceed 60 characters (a Zulip limitation).
@@ -313,3 +724,88 @@ fn zulip_topic_from_issue(issue: &ZulipGitHubReference) -> String {
         _ => format!("{} {}", issue.title, topic_ref),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn raise_and_resolve() {
+        let mut state = MajorChangeState::default();
+        assert!(!state.has_open_concerns());
+        state.raise_concern("abi", "alice", "url1").unwrap();
+        assert!(state.has_open_concerns());
+        assert_eq!(
+            state.raise_concern("abi", "bob", "url2"),
+            Err("A concern named `abi` is already open.".to_string())
+        );
+        assert_eq!(
+            state.resolve_concern("other", "bob"),
+            Err("There is no open concern named `other`.".to_string())
+        );
+        state.resolve_concern("abi", "bob").unwrap();
+        assert!(!state.has_open_concerns());
+        assert_eq!(
+            state.resolve_concern("abi", "bob"),
+            Err("There is no open concern named `abi`.".to_string())
+        );
+    }
+
+    #[test]
+    fn raise_resolved_again() {
+        let mut state = MajorChangeState::default();
+        state.raise_concern("abi", "alice", "url1").unwrap();
+        state.resolve_concern("abi", "bob").unwrap();
+        state.raise_concern("abi", "carol", "url2").unwrap();
+        assert!(state.has_open_concerns());
+        assert_eq!(state.concerns.len(), 1);
+        let concern = &state.concerns[0];
+        assert_eq!(concern.author, "carol");
+        assert_eq!(concern.comment_url, "url2");
+        assert_eq!(concern.resolved_by, None);
+    }
+
+    #[test]
+    fn overdue_acceptance() {
+        let accept_at = Utc.ymd(2023, 1, 10).and_hms(0, 0, 0);
+        let before = Utc.ymd(2023, 1, 9).and_hms(0, 0, 0);
+        let after = Utc.ymd(2023, 1, 11).and_hms(0, 0, 0);
+        let mut state = MajorChangeState {
+            accept_at: Some(accept_at),
+            ..Default::default()
+        };
+        state.raise_concern("abi", "alice", "url").unwrap();
+        state.raise_concern("docs", "bob", "url").unwrap();
+        assert_eq!(state.overdue_acceptance(after), None);
+        state.resolve_concern("abi", "carol").unwrap();
+        assert_eq!(state.overdue_acceptance(after), None);
+        state.resolve_concern("docs", "carol").unwrap();
+        assert_eq!(state.overdue_acceptance(after), Some(accept_at));
+        // The job that is already scheduled accepts it.
+        assert_eq!(state.overdue_acceptance(before), None);
+        state.accept_at = None;
+        assert_eq!(state.overdue_acceptance(after), None);
+    }
+
+    #[test]
+    fn markdown() {
+        let mut state = MajorChangeState::default();
+        state
+            .raise_concern("abi", "alice", "https://example.com/1")
+            .unwrap();
+        state
+            .raise_concern("needs docs", "bob", "https://example.com/2")
+            .unwrap();
+        state.resolve_concern("abi", "carol").unwrap();
+        assert_eq!(
+            state.concerns_markdown(),
+            "\n### Concerns\n\
+            \n- ~~abi~~ resolved by @carol (raised by @alice [here](https://example.com/1))\
+            \n- needs docs (raised by @bob [here](https://example.com/2))\
+            \n\nThe proposal won't be accepted while a concern is open. \
+            Use `@rustbot concern <name>` to raise a concern, and `@rustbot resolve <name>` \
+            to resolve it."
+        );
+    }
+}
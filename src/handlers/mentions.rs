@@ -5,7 +5,7 @@
 use crate::{
     config::{MentionsConfig, MentionsPathConfig},
     db::issue_data::IssueData,
-    github::{files_changed, IssuesAction, IssuesEvent},
+    github::{files_changed, IssuesAction, IssuesEvent, PullRequestData},
     handlers::Context,
 };
 use anyhow::Context as _;
@@ -28,6 +28,7 @@ struct MentionState {
 pub(super) async fn parse_input(
     ctx: &Context,
     event: &IssuesEvent,
+    pr: &PullRequestData<'_>,
     config: Option<&MentionsConfig>,
 ) -> Result<Option<MentionsInput>, String> {
     let config = match config {
@@ -50,8 +51,7 @@ pub(super) async fn parse_input(
         return Ok(None);
     }
 
-    if let Some(diff) = event
-        .issue
+    if let Some(diff) = pr
         .diff(&ctx.github)
         .await
         .map_err(|e| {
@@ -59,7 +59,7 @@ pub(super) async fn parse_input(
         })
         .unwrap_or_default()
     {
-        let files = files_changed(&diff);
+        let files = files_changed(diff);
         let file_paths: Vec<_> = files.iter().map(|p| Path::new(p)).collect();
         let to_mention: Vec<_> = config
             .paths
@@ -3,7 +3,6 @@ use crate::{
     handlers::Context,
 };
 use anyhow::Context as _;
-use reqwest::StatusCode;
 use tracing as log;
 
 pub async fn handle(ctx: &Context, event: &Event) -> anyhow::Result<()> {
@@ -66,33 +65,12 @@ pub async fn handle(ctx: &Context, event: &Event) -> anyhow::Result<()> {
 }
 
 async fn get_version_standalone(ctx: &Context, merge_sha: &str) -> anyhow::Result<Option<String>> {
-    let resp = ctx
+    // A 404 is not treated as a failure, we'll try another way to retrieve the version.
+    let version = ctx
         .github
-        .raw()
-        .get(&format!(
-            "https://raw.githubusercontent.com/rust-lang/rust/{}/src/version",
-            merge_sha
-        ))
-        .send()
+        .raw_file("rust-lang/rust", merge_sha, "src/version")
         .await
         .with_context(|| format!("retrieving src/version for {}", merge_sha))?;
 
-    match resp.status() {
-        StatusCode::OK => {}
-        // Don't treat a 404 as a failure, we'll try another way to retrieve the version.
-        StatusCode::NOT_FOUND => return Ok(None),
-        status => anyhow::bail!(
-            "unexpected status code {} while retrieving src/version for {}",
-            status,
-            merge_sha
-        ),
-    }
-
-    Ok(Some(
-        resp.text()
-            .await
-            .with_context(|| format!("deserializing src/version for {}", merge_sha))?
-            .trim()
-            .to_string(),
-    ))
+    Ok(version.map(|version| String::from_utf8_lossy(&version).trim().to_string()))
 }
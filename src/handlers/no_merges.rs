@@ -4,7 +4,7 @@
 use crate::{
     config::NoMergesConfig,
     db::issue_data::IssueData,
-    github::{IssuesAction, IssuesEvent, Label},
+    github::{IssuesAction, IssuesEvent, Label, PullRequestData},
     handlers::Context,
 };
 use anyhow::Context as _;
@@ -29,6 +29,7 @@ struct NoMergesState {
 pub(super) async fn parse_input(
     ctx: &Context,
     event: &IssuesEvent,
+    pr: &PullRequestData<'_>,
     config: Option<&NoMergesConfig>,
 ) -> Result<Option<NoMergesInput>, String> {
     if !matches!(
@@ -56,8 +57,7 @@ pub(super) async fn parse_input(
     }
 
     let mut merge_commits = HashSet::new();
-    let commits = event
-        .issue
+    let commits = pr
         .commits(&ctx.github)
         .await
         .map_err(|e| {
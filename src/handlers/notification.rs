@@ -6,6 +6,7 @@
 
 use crate::db::notifications;
 use crate::{
+    config::NotificationConfig,
     github::{self, Event},
     handlers::Context,
 };
@@ -14,7 +15,11 @@ use std::collections::HashSet;
 use std::convert::{TryFrom, TryInto};
 use tracing as log;
 
-pub async fn handle(ctx: &Context, event: &Event) -> anyhow::Result<()> {
+pub async fn handle(
+    ctx: &Context,
+    event: &Event,
+    config: Option<&NotificationConfig>,
+) -> anyhow::Result<()> {
     let body = match event.comment_body() {
         Some(v) => v,
         // Skip events that don't have comment bodies associated
@@ -41,14 +46,22 @@ pub async fn handle(ctx: &Context, event: &Event) -> anyhow::Result<()> {
         .into_iter()
         .collect::<HashSet<_>>();
 
-    // FIXME: Remove this hardcoding. Ideally we need organization-wide
-    // configuration, but it's unclear where to put it.
-    if event.issue().unwrap().repository().organization == "serde-rs" {
-        // Only add dtolnay on new issues/PRs, not on comments to old PRs and
-        // issues.
-        if let Event::Issue(e) = event {
-            if e.action == github::IssuesAction::Opened {
-                caps.insert("dtolnay");
+    // Only notify on new issues/PRs, not on comments to old PRs and issues.
+    if let Event::Issue(e) = event {
+        if e.action == github::IssuesAction::Opened {
+            match config {
+                Some(config) => caps.extend(
+                    config
+                        .mention_on_open
+                        .iter()
+                        .map(|user| user.trim_start_matches('@')),
+                ),
+                // FIXME: Remove once serde-rs sets `mention_on_open` in its
+                // configuration, which replaces this hardcoding.
+                None if e.issue.repository().organization == "serde-rs" => {
+                    caps.insert("dtolnay");
+                }
+                None => {}
             }
         }
     }
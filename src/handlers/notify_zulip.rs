@@ -1,6 +1,6 @@
 use crate::{
     config::{NotifyZulipConfig, NotifyZulipLabelConfig},
-    github::{Issue, IssuesAction, IssuesEvent, Label},
+    github::{Issue, IssuesAction, IssuesEvent, Label, PullRequestData},
     handlers::Context,
 };
 use tracing as log;
@@ -24,6 +24,7 @@ pub(super) enum NotificationType {
 pub(super) async fn parse_input(
     _ctx: &Context,
     event: &IssuesEvent,
+    _pr: &PullRequestData<'_>,
     config: Option<&NotifyZulipConfig>,
 ) -> Result<Option<Vec<NotifyZulipInput>>, String> {
     let config = match config {
@@ -0,0 +1,292 @@
+//! Purpose: Block merging PRs that modify sensitive paths until someone from
+//! a specific group approves them.
+//!
+//! Each path prefix in the `[required-reviews]` table lists the users, teams
+//! or ad-hoc groups that must approve PRs modifying it. The result is
+//! published as a commit status on the head of the PR, which is pending until
+//! every matching path has an approval. Branch protection can then require
+//! the `triagebot/required-reviews` status to pass before merging.
+
+use crate::{
+    config::{AssignConfig, RequiredReviewPathConfig, RequiredReviewsConfig},
+    github::{
+        files_changed, Comment, CommitStatusState, Event, IssuesAction, PullRequestData,
+        PullRequestReviewState,
+    },
+    handlers::Context,
+};
+use rust_team_data::v1::Teams;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use tracing as log;
+
+const STATUS_CONTEXT: &str = "triagebot/required-reviews";
+
+/// GitHub rejects commit status descriptions longer than this.
+const MAX_DESCRIPTION_LEN: usize = 140;
+
+pub(super) async fn handle(
+    ctx: &Context,
+    event: &Event,
+    pr: &PullRequestData<'_>,
+    config: &RequiredReviewsConfig,
+    assign: Option<&AssignConfig>,
+) -> anyhow::Result<()> {
+    match event {
+        Event::Issue(e)
+            if matches!(
+                e.action,
+                IssuesAction::Opened
+                    | IssuesAction::Synchronize
+                    | IssuesAction::Reopened
+                    | IssuesAction::ReadyForReview
+            ) => {}
+        // A review was submitted, edited or dismissed.
+        Event::IssueComment(e) if e.comment.pr_review_state.is_some() => {}
+        _ => return Ok(()),
+    }
+    let issue = pr.issue();
+    if !issue.is_pr() || !issue.is_open() || issue.head.is_none() {
+        return Ok(());
+    }
+    let Some(diff) = pr.diff(&ctx.github).await? else {
+        return Ok(());
+    };
+    let required = required_paths(config, &files_changed(diff));
+
+    // The status is set even if no path requires a review, so that branch
+    // protection can require it on every PR.
+    let (state, description) = if required.is_empty() {
+        (
+            CommitStatusState::Success,
+            "No approvals are required".to_string(),
+        )
+    } else {
+        let reviews = issue.reviews(&ctx.github).await?;
+        let approvers = approvers(&reviews, &issue.user.login);
+        let teams = crate::team_data::teams(&ctx.github).await?;
+        let empty = HashMap::new();
+        let groups = assign.map_or(&empty, |assign| &assign.adhoc_groups);
+        let org = &issue.repository().organization;
+        let missing: Vec<&str> = required
+            .iter()
+            .filter(|(_, rule)| {
+                let reviewers = expand_reviewers(&rule.reviewers, org, &teams, groups);
+                !approvers.iter().any(|a| reviewers.contains(a))
+            })
+            .map(|(path, _)| *path)
+            .collect();
+        if missing.is_empty() {
+            (
+                CommitStatusState::Success,
+                "Approved by the required reviewers".to_string(),
+            )
+        } else {
+            let mut description = format!("Waiting for approval of {}", missing.join(", "));
+            if description.len() > MAX_DESCRIPTION_LEN {
+                let mut end = MAX_DESCRIPTION_LEN - 3;
+                while !description.is_char_boundary(end) {
+                    end -= 1;
+                }
+                description.truncate(end);
+                description.push_str("...");
+            }
+            (CommitStatusState::Pending, description)
+        }
+    };
+    issue
+        .set_head_status(&ctx.github, state, STATUS_CONTEXT, &description)
+        .await
+}
+
+/// Returns the configured paths that the modified files are in, sorted by
+/// path.
+fn required_paths<'a>(
+    config: &'a RequiredReviewsConfig,
+    files: &[&str],
+) -> Vec<(&'a str, &'a RequiredReviewPathConfig)> {
+    let mut required: Vec<_> = config
+        .paths
+        .iter()
+        .filter(|(path, _)| {
+            files
+                .iter()
+                .any(|file| Path::new(file).starts_with(Path::new(path)))
+        })
+        .map(|(path, rule)| (path.as_str(), rule))
+        .collect();
+    required.sort_by_key(|(path, _)| *path);
+    required
+}
+
+/// Returns the lowercase names of the users whose latest review approves the
+/// PR, excluding its author.
+fn approvers(reviews: &[Comment], author: &str) -> HashSet<String> {
+    let mut latest: HashMap<String, &PullRequestReviewState> = HashMap::new();
+    for review in reviews {
+        match &review.pr_review_state {
+            // Comments don't change whether someone approved.
+            Some(PullRequestReviewState::Commented | PullRequestReviewState::Pending) | None => {}
+            Some(state) => {
+                latest.insert(review.user.login.to_lowercase(), state);
+            }
+        }
+    }
+    latest
+        .into_iter()
+        .filter(|(user, state)| {
+            **state == PullRequestReviewState::Approved && *user != author.to_lowercase()
+        })
+        .map(|(user, _)| user)
+        .collect()
+}
+
+/// Expands the teams and ad-hoc groups among `names` into the lowercase
+/// names of their members.
+///
+/// As in CODEOWNERS, an `org/team` name only refers to a team or group of
+/// rust-lang or of the repository's organization `org`. Names of other
+/// organizations can't be resolved, so no one can approve for them.
+fn expand_reviewers(
+    names: &[String],
+    org: &str,
+    teams: &Teams,
+    groups: &HashMap<String, Vec<String>>,
+) -> HashSet<String> {
+    let mut reviewers = HashSet::new();
+    let mut seen = HashSet::new();
+    let mut queue: Vec<&str> = names.iter().map(|n| n.as_str()).collect();
+    while let Some(name) = queue.pop() {
+        let name = name.strip_prefix('@').unwrap_or(name);
+        let group = match name.split_once('/') {
+            None => name,
+            Some((team_org, group))
+                if team_org.eq_ignore_ascii_case(org) || team_org == "rust-lang" =>
+            {
+                group
+            }
+            Some(_) => {
+                log::debug!("can't resolve required reviewer `{name}` of another organization");
+                continue;
+            }
+        };
+        if let Some(members) = groups.get(group) {
+            if seen.insert(group) {
+                queue.extend(members.iter().map(|m| m.as_str()));
+            }
+        } else if let Some(team) = teams.teams.get(group) {
+            reviewers.extend(team.members.iter().map(|m| m.github.to_lowercase()));
+        } else if !name.contains('/') {
+            reviewers.insert(name.to_lowercase());
+        }
+    }
+    reviewers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn review(user: &str, state: &str) -> Comment {
+        serde_json::from_value(serde_json::json!({
+            "body": null,
+            "html_url": "",
+            "user": {"login": user, "id": 1},
+            "submitted_at": "2023-01-01T00:00:00Z",
+            "state": state,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn latest_review_wins() {
+        let reviews = [
+            review("alice", "APPROVED"),
+            review("bob", "APPROVED"),
+            review("bob", "CHANGES_REQUESTED"),
+            review("carol", "CHANGES_REQUESTED"),
+            review("carol", "APPROVED"),
+            review("carol", "COMMENTED"),
+            review("Dave", "APPROVED"),
+            review("dave", "DISMISSED"),
+            review("author", "APPROVED"),
+        ];
+        let mut approvers: Vec<_> = approvers(&reviews, "Author").into_iter().collect();
+        approvers.sort();
+        assert_eq!(approvers, ["alice", "carol"]);
+    }
+
+    #[test]
+    fn paths() {
+        let config: RequiredReviewsConfig = toml::from_str(
+            r#"
+            "src/tools/cargo" = { reviewers = ["cargo"] }
+            "library/core/src/intrinsics" = { reviewers = ["@alice"] }
+            "library/std" = { reviewers = ["libs"] }
+            "#,
+        )
+        .unwrap();
+        let required = required_paths(
+            &config,
+            &[
+                "src/tools/cargo/src/lib.rs",
+                "library/core/src/intrinsics.rs",
+                "library/core/src/intrinsics/mod.rs",
+                "library/stdarch/lib.rs",
+            ],
+        );
+        let paths: Vec<_> = required.iter().map(|(path, _)| *path).collect();
+        assert_eq!(paths, ["library/core/src/intrinsics", "src/tools/cargo"]);
+    }
+
+    #[test]
+    fn expand() {
+        let teams: Teams = serde_json::from_value(serde_json::json!({
+            "cargo": {
+                "name": "cargo",
+                "kind": "team",
+                "members": [
+                    {"name": "Eh2406", "github": "Eh2406", "github_id": 1, "is_lead": false},
+                ],
+                "alumni": [],
+            }
+        }))
+        .unwrap();
+        let groups = HashMap::from([(
+            "intrinsics".to_string(),
+            vec!["rust-lang/cargo".to_string(), "@Alice".to_string()],
+        )]);
+        let mut reviewers: Vec<_> = expand_reviewers(
+            &["intrinsics".to_string(), "bob".to_string()],
+            "rust-lang",
+            &teams,
+            &groups,
+        )
+        .into_iter()
+        .collect();
+        reviewers.sort();
+        assert_eq!(reviewers, ["alice", "bob", "eh2406"]);
+
+        // Teams and groups of the repository's own organization resolve too.
+        let reviewers = expand_reviewers(
+            &["Rust-Embedded/intrinsics".to_string()],
+            "rust-embedded",
+            &teams,
+            &groups,
+        );
+        assert_eq!(reviewers.len(), 2);
+
+        // Other organizations can't be resolved, even if they have a team of
+        // the same name.
+        let reviewers = expand_reviewers(
+            &[
+                "other-org/cargo".to_string(),
+                "@other-org/intrinsics".to_string(),
+            ],
+            "rust-lang",
+            &teams,
+            &groups,
+        );
+        assert!(reviewers.is_empty(), "{reviewers:?}");
+    }
+}
@@ -1,5 +1,28 @@
-use crate::github::{Issue, IssueCommentAction, IssueCommentEvent, Label, PullRequestReviewState};
+//! Purpose: Keep the status labels of a PR in sync with its reviews.
+//!
+//! When an assignee requests changes, the review labels are replaced with
+//! `reviewed_label`. If `requeue_on_push` or `requeue_on_review_request` is
+//! set, the reverse happens when the PR author pushes new commits or
+//! re-requests a review.
+
+use crate::db::issue_data::IssueData;
+use crate::github::{
+    Issue, IssueCommentAction, IssueCommentEvent, IssuesAction, IssuesEvent, Label,
+    PullRequestReviewState,
+};
 use crate::{config::ReviewSubmittedConfig, github::Event, handlers::Context};
+use serde::{Deserialize, Serialize};
+use tracing as log;
+
+const REVIEW_SUBMITTED_KEY: &str = "review_submitted";
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct ReviewSubmittedState {
+    /// Whether the PR has been put back in the review queue since
+    /// `reviewed_label` was last added, so that a series of pushes only
+    /// changes the labels once.
+    requeued: bool,
+}
 
 pub(crate) async fn handle(
     ctx: &Context,
@@ -39,5 +62,202 @@ pub(crate) async fn handle(
         }
     }
 
+    if let Event::Issue(event) = event {
+        if config.requeue_on_push || config.requeue_on_review_request {
+            handle_requeue(ctx, event, config).await?;
+        }
+    }
+
     Ok(())
 }
+
+/// What an issue event means for putting a PR back in the review queue.
+#[derive(Debug, PartialEq, Eq)]
+enum RequeueAction {
+    /// `reviewed_label` was added, so the PR may be requeued again.
+    Reset,
+    /// The author pushed or re-requested a review of a reviewed PR.
+    Requeue,
+    Ignore,
+}
+
+impl ReviewSubmittedState {
+    /// Updates the state for the given event, returning whether the labels
+    /// of the PR should be changed to requeue it.
+    fn apply(&mut self, requeue: RequeueAction) -> bool {
+        match requeue {
+            RequeueAction::Reset => self.requeued = false,
+            // Only the first push after a review changes the labels.
+            RequeueAction::Requeue if !self.requeued => {
+                self.requeued = true;
+                return true;
+            }
+            RequeueAction::Requeue | RequeueAction::Ignore => {}
+        }
+        false
+    }
+}
+
+fn requeue_action(event: &IssuesEvent, config: &ReviewSubmittedConfig) -> RequeueAction {
+    if !event.issue.is_pr() {
+        return RequeueAction::Ignore;
+    }
+
+    // Any time `reviewed_label` is added, whether by this handler or by hand,
+    // the PR may be requeued again.
+    if event.action == IssuesAction::Labeled {
+        if event.label.as_ref().map(|l| l.name.as_str()) == Some(config.reviewed_label.as_str()) {
+            return RequeueAction::Reset;
+        }
+        return RequeueAction::Ignore;
+    }
+
+    let trigger = match event.action {
+        IssuesAction::Synchronize => config.requeue_on_push,
+        IssuesAction::ReviewRequested => config.requeue_on_review_request,
+        _ => false,
+    };
+    if !trigger
+        || event.sender.login != event.issue.user.login
+        || event.issue.draft
+        || !event
+            .issue
+            .labels()
+            .iter()
+            .any(|l| l.name == config.reviewed_label)
+    {
+        return RequeueAction::Ignore;
+    }
+    RequeueAction::Requeue
+}
+
+async fn handle_requeue(
+    ctx: &Context,
+    event: &IssuesEvent,
+    config: &ReviewSubmittedConfig,
+) -> anyhow::Result<()> {
+    let requeue = requeue_action(event, config);
+    if requeue == RequeueAction::Ignore {
+        return Ok(());
+    }
+
+    let mut client = ctx.db.get().await;
+    let mut state: IssueData<'_, ReviewSubmittedState> =
+        IssueData::load(&mut client, &event.issue, REVIEW_SUBMITTED_KEY).await?;
+    let was_requeued = state.data.requeued;
+    if state.data.apply(requeue) {
+        event
+            .issue
+            .remove_label(&ctx.github, &config.reviewed_label)
+            .await?;
+        event
+            .issue
+            .add_labels(
+                &ctx.github,
+                config
+                    .review_labels
+                    .iter()
+                    .map(|name| Label { name: name.clone() })
+                    .collect(),
+            )
+            .await?;
+    } else if was_requeued && state.data.requeued {
+        log::trace!(
+            "{} was already requeued since it was reviewed",
+            event.issue.global_id()
+        );
+    }
+    if state.data.requeued != was_requeued {
+        state.save().await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> ReviewSubmittedConfig {
+        ReviewSubmittedConfig {
+            review_labels: vec!["S-waiting-on-review".into()],
+            reviewed_label: "S-waiting-on-author".into(),
+            requeue_on_push: true,
+            requeue_on_review_request: false,
+        }
+    }
+
+    fn event(action: &str, sender: &str, draft: bool, label: Option<&str>) -> IssuesEvent {
+        serde_json::from_value(serde_json::json!({
+            "action": action,
+            "issue": {
+                "number": 1,
+                "title": "",
+                "body": "",
+                "html_url": "",
+                "user": {"login": "author", "id": 1},
+                "labels": [{"name": "S-waiting-on-author"}],
+                "assignees": [],
+                "comments_url": "https://api.github.com/repos/rust-lang/rust/issues/1/comments",
+                "state": "open",
+                "draft": draft,
+                "created_at": "2023-01-01T00:00:00Z",
+                "updated_at": "2023-01-01T00:00:00Z",
+                "pull_request": {},
+            },
+            "repository": {"full_name": "rust-lang/rust", "default_branch": "master"},
+            "label": label.map(|name| serde_json::json!({"name": name})),
+            "sender": {"login": sender, "id": 2},
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn triggers() {
+        let mut config = config();
+        let push = event("synchronize", "author", false, None);
+        let review_request = event("review_requested", "author", false, None);
+        assert_eq!(requeue_action(&push, &config), RequeueAction::Requeue);
+        assert_eq!(
+            requeue_action(&review_request, &config),
+            RequeueAction::Ignore
+        );
+
+        config.requeue_on_push = false;
+        config.requeue_on_review_request = true;
+        assert_eq!(requeue_action(&push, &config), RequeueAction::Ignore);
+        assert_eq!(
+            requeue_action(&review_request, &config),
+            RequeueAction::Requeue
+        );
+    }
+
+    #[test]
+    fn author_only() {
+        let push = event("synchronize", "reviewer", false, None);
+        assert_eq!(requeue_action(&push, &config()), RequeueAction::Ignore);
+    }
+
+    #[test]
+    fn draft() {
+        let push = event("synchronize", "author", true, None);
+        assert_eq!(requeue_action(&push, &config()), RequeueAction::Ignore);
+    }
+
+    #[test]
+    fn requeued_once_until_reviewed() {
+        let config = config();
+        let push = event("synchronize", "author", false, None);
+        let reviewed = event("labeled", "reviewer", false, Some("S-waiting-on-author"));
+        let other_label = event("labeled", "reviewer", false, Some("T-compiler"));
+        assert_eq!(requeue_action(&reviewed, &config), RequeueAction::Reset);
+        assert_eq!(requeue_action(&other_label, &config), RequeueAction::Ignore);
+
+        let mut state = ReviewSubmittedState::default();
+        assert!(state.apply(requeue_action(&push, &config)));
+        assert!(!state.apply(requeue_action(&push, &config)));
+        assert!(!state.apply(requeue_action(&other_label, &config)));
+        assert!(!state.apply(requeue_action(&push, &config)));
+        assert!(!state.apply(requeue_action(&reviewed, &config)));
+        assert!(state.apply(requeue_action(&push, &config)));
+    }
+}
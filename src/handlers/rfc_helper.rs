@@ -1,9 +1,9 @@
 use crate::{
-    github::{Event, IssuesAction, IssuesEvent},
+    github::{Event, IssuesAction, IssuesEvent, PullRequestData},
     handlers::Context,
 };
 
-pub async fn handle(ctx: &Context, event: &Event) -> anyhow::Result<()> {
+pub async fn handle(ctx: &Context, event: &Event, pr: &PullRequestData<'_>) -> anyhow::Result<()> {
     let e = if let Event::Issue(e) = event {
         e
     } else {
@@ -15,16 +15,20 @@ pub async fn handle(ctx: &Context, event: &Event) -> anyhow::Result<()> {
         return Ok(());
     }
 
-    if let Err(e) = add_rendered_link(&ctx, &e).await {
+    if let Err(e) = add_rendered_link(&ctx, &e, pr).await {
         tracing::error!("Error adding rendered link: {:?}", e);
     }
 
     Ok(())
 }
 
-async fn add_rendered_link(ctx: &Context, e: &IssuesEvent) -> anyhow::Result<()> {
+async fn add_rendered_link(
+    ctx: &Context,
+    e: &IssuesEvent,
+    pr: &PullRequestData<'_>,
+) -> anyhow::Result<()> {
     if e.action == IssuesAction::Opened {
-        let files = e.issue.files(&ctx.github).await?;
+        let files = pr.files(&ctx.github).await?;
 
         if let Some(file) = files.iter().find(|f| f.filename.starts_with("text/")) {
             if !e.issue.body.contains("[Rendered]") {
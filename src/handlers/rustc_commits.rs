@@ -1,10 +1,12 @@
-use crate::db::jobs::JobSchedule;
+use crate::db::jobs::{JobSchedule, RetryPolicy};
 use crate::db::rustc_commits;
 use crate::db::rustc_commits::get_missing_commits;
+use crate::jobs::JobHandler;
 use crate::{
     github::{self, Event},
     handlers::Context,
 };
+use async_trait::async_trait;
 use cron::Schedule;
 use std::collections::VecDeque;
 use std::convert::TryInto;
@@ -155,13 +157,31 @@ pub async fn synchronize_commits_inner(ctx: &Context, starter: Option<(String, u
 
 pub fn job() -> JobSchedule {
     JobSchedule {
-        name: "rustc_commits".to_string(),
+        name: RustcCommitsJob::NAME.to_string(),
         // Every 30 minutes...
         schedule: Schedule::from_str("* 0,30 * * * * *").unwrap(),
         metadata: serde_json::Value::Null,
     }
 }
 
+pub struct RustcCommitsJob;
+
+#[async_trait]
+impl JobHandler for RustcCommitsJob {
+    const NAME: &'static str = "rustc_commits";
+    type Metadata = ();
+
+    fn retry_policy(&self) -> RetryPolicy {
+        // The next run catches up anyway.
+        RetryPolicy::never()
+    }
+
+    async fn run(&self, ctx: &Context, _metadata: ()) -> anyhow::Result<()> {
+        synchronize_commits_inner(ctx, None).await;
+        Ok(())
+    }
+}
+
 #[derive(Debug, serde::Deserialize)]
 struct BorsMessage {
     #[serde(rename = "type")]
@@ -1,10 +1,14 @@
 //! Purpose: Allow the use of single words shortcut to do specific actions on GitHub via comments.
 //!
+//! The shortcuts are configured in the `[shortcut]` section; each one sets a
+//! status label, removing the labels of the other shortcuts, and can also
+//! request a new review or reassign the pull request to its reviewers.
+//!
 //! Parsing is done in the `parser::command::shortcut` module.
 
 use crate::{
-    config::ShortcutConfig,
-    github::{Event, Label},
+    config::{ShortcutConfig, ShortcutReviewerAction},
+    github::{Event, Issue, Label, Selection},
     handlers::Context,
     interactions::ErrorComment,
 };
@@ -12,33 +16,33 @@ use parser::command::shortcut::ShortcutCommand;
 
 pub(super) async fn handle_command(
     ctx: &Context,
-    _config: &ShortcutConfig,
+    config: &ShortcutConfig,
     event: &Event,
     input: ShortcutCommand,
 ) -> anyhow::Result<()> {
     let issue = event.issue().unwrap();
-    // NOTE: if shortcuts available to issues are created, they need to be allowed here
-    if !issue.is_pr() {
-        let msg = format!("The \"{:?}\" shortcut only works on pull requests.", input);
-        let cmnt = ErrorComment::new(&issue, msg);
+    let Some((name, shortcut)) = config.get_by_name(&input.name) else {
+        // The parser only recognizes configured shortcuts.
+        anyhow::bail!("unknown shortcut {:?}", input.name);
+    };
+    if issue.is_pr() && !shortcut.pull_requests {
+        let msg = format!("The \"{}\" shortcut only works on issues.", name);
+        let cmnt = ErrorComment::new(issue, msg);
+        cmnt.post(&ctx.github).await?;
+        return Ok(());
+    }
+    if !issue.is_pr() && !shortcut.issues {
+        let msg = format!("The \"{}\" shortcut only works on pull requests.", name);
+        let cmnt = ErrorComment::new(issue, msg);
         cmnt.post(&ctx.github).await?;
         return Ok(());
     }
 
     let issue_labels = issue.labels();
-    let waiting_on_review = "S-waiting-on-review";
-    let waiting_on_author = "S-waiting-on-author";
-    let blocked = "S-blocked";
-    let status_labels = [waiting_on_review, waiting_on_author, blocked];
-
-    let add = match input {
-        ShortcutCommand::Ready => waiting_on_review,
-        ShortcutCommand::Author => waiting_on_author,
-        ShortcutCommand::Blocked => blocked,
-    };
+    let add = shortcut.label.as_str();
 
     if !issue_labels.iter().any(|l| l.name == add) {
-        for remove in status_labels {
+        for remove in config.exclusive_labels(shortcut) {
             if remove != add {
                 issue.remove_label(&ctx.github, remove).await?;
             }
@@ -53,5 +57,53 @@ pub(super) async fn handle_command(
             .await?;
     }
 
+    if let (true, Some(action)) = (issue.is_pr(), shortcut.reviewer_action) {
+        handle_reviewer_action(ctx, issue, action).await?;
+    }
+
+    Ok(())
+}
+
+async fn handle_reviewer_action(
+    ctx: &Context,
+    issue: &Issue,
+    action: ShortcutReviewerAction,
+) -> anyhow::Result<()> {
+    let mut reviewers: Vec<String> = issue
+        .reviews(&ctx.github)
+        .await?
+        .into_iter()
+        .map(|review| review.user.login)
+        .filter(|login| *login != issue.user.login)
+        .collect();
+    reviewers.sort();
+    reviewers.dedup();
+    if reviewers.is_empty() {
+        return Ok(());
+    }
+    let reviewers: Vec<&str> = reviewers.iter().map(|r| r.as_str()).collect();
+
+    match action {
+        ShortcutReviewerAction::RerequestReview => {
+            issue.request_reviewers(&ctx.github, &reviewers).await?;
+        }
+        ShortcutReviewerAction::Reassign => {
+            for reviewer in &reviewers {
+                if !issue.contain_assignee(reviewer) {
+                    issue.add_assignee(&ctx.github, reviewer).await?;
+                }
+            }
+            for assignee in &issue.assignees {
+                if !reviewers
+                    .iter()
+                    .any(|r| r.eq_ignore_ascii_case(&assignee.login))
+                {
+                    issue
+                        .remove_assignees(&ctx.github, Selection::One(&assignee.login))
+                        .await?;
+                }
+            }
+        }
+    }
     Ok(())
 }
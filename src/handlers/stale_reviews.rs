@@ -0,0 +1,350 @@
+//! A scheduled job to ping reviewers who haven't looked at a PR assigned to
+//! them in a while, and eventually assign someone else.
+//!
+//! This is configured with the `[assign.stale_review]` table. The PRs to
+//! check are the open PRs with the configured label in the repositories of
+//! the `review_assignments` table, which is maintained by the `assign`
+//! handler. The table also tells when a reviewer was assigned; for PRs
+//! assigned before it existed, the issue events are used instead.
+
+use super::assign::{
+    find_reviewers_from_diff, find_reviewers_from_names, load_codeowners, reviewers, set_reviewers,
+    Explanation,
+};
+use crate::config::{self, AssignConfig, StaleReviewConfig};
+use crate::db::issue_data::IssueData;
+use crate::db::jobs::{JobSchedule, RetryPolicy};
+use crate::db::review_assignments::{self, OpenAssignment};
+use crate::github::{Issue, PullRequestData, Query, Repository};
+use crate::handlers::Context;
+use crate::jobs::JobHandler;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use cron::Schedule;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use tracing as log;
+
+const STALE_REVIEW_KEY: &str = "stale_review";
+
+const PING_MESSAGE: &str = "@{reviewer}, this PR has been waiting on your review for {days} days. \
+If you won't be able to review it soon, please reassign it with `r?`.";
+
+const REASSIGN_MESSAGE: &str =
+    "@{reviewer} hasn't commented on or reviewed this PR in {days} days, \
+so it has been reassigned to @{new_reviewer}.";
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct StaleReviewState {
+    /// The reviewer that was last pinged about this PR.
+    pinged_reviewer: Option<String>,
+    pinged_at: Option<DateTime<Utc>>,
+}
+
+pub fn job() -> JobSchedule {
+    JobSchedule {
+        name: StaleReviewsJob::NAME.to_string(),
+        // Once a day, during the European afternoon and the American morning.
+        schedule: Schedule::from_str("0 0 14 * * * *").unwrap(),
+        metadata: serde_json::Value::Null,
+    }
+}
+
+pub struct StaleReviewsJob;
+
+#[async_trait]
+impl JobHandler for StaleReviewsJob {
+    const NAME: &'static str = "stale_reviews";
+    type Metadata = ();
+
+    fn retry_policy(&self) -> RetryPolicy {
+        // Retrying could ping reviewers of the PRs checked before the
+        // failure twice in a day, and the job runs daily anyway.
+        RetryPolicy::never()
+    }
+
+    async fn run(&self, ctx: &Context, _metadata: ()) -> anyhow::Result<()> {
+        handle_job(ctx).await
+    }
+}
+
+async fn handle_job(ctx: &Context) -> anyhow::Result<()> {
+    let (repos, assignments) = {
+        let db = ctx.db.get().await;
+        (
+            review_assignments::repos(&db).await?,
+            review_assignments::open_assignments(&db).await?,
+        )
+    };
+    let mut by_pr: HashMap<(String, i32), Vec<OpenAssignment>> = HashMap::new();
+    for assignment in assignments {
+        by_pr
+            .entry((assignment.repo.clone(), assignment.issue_number))
+            .or_default()
+            .push(assignment);
+    }
+    for repo in repos {
+        if let Err(e) = check_repo(ctx, &repo, &by_pr).await {
+            log::warn!("failed to check stale reviews in {repo}: {e:?}");
+        }
+    }
+    Ok(())
+}
+
+async fn check_repo(
+    ctx: &Context,
+    repo: &str,
+    assignments: &HashMap<(String, i32), Vec<OpenAssignment>>,
+) -> anyhow::Result<()> {
+    let repo = ctx.github.repository(repo).await?;
+    let config = match config::get(&ctx.github, &repo).await {
+        Ok(config) => config,
+        Err(e) => {
+            log::trace!("skipping stale reviews in {}: {e}", repo.full_name);
+            return Ok(());
+        }
+    };
+    let Some(assign) = &config.assign else {
+        return Ok(());
+    };
+    let Some(stale) = &assign.stale_review else {
+        return Ok(());
+    };
+    // PRs are found by label rather than from the `review_assignments` table,
+    // which doesn't know about PRs assigned before it was created.
+    let prs = repo
+        .get_issues(
+            &ctx.github,
+            &Query {
+                filters: vec![("state", "open"), ("is", "pull-request")],
+                include_labels: vec![&stale.label],
+                exclude_labels: vec![],
+            },
+        )
+        .await?;
+    for pr in prs {
+        let recorded = assignments
+            .get(&(repo.full_name.clone(), pr.number as i32))
+            .map_or(&[][..], |a| a.as_slice());
+        if let Err(e) = check_pr(ctx, &repo, assign, stale, pr.number, recorded).await {
+            log::warn!(
+                "failed to check stale review of {}#{}: {e:?}",
+                repo.full_name,
+                pr.number
+            );
+        }
+    }
+    Ok(())
+}
+
+/// What to do about a PR whose reviewer has been inactive for a while.
+#[derive(Debug, PartialEq, Eq)]
+enum StaleAction {
+    Nothing,
+    Ping,
+    Reassign,
+}
+
+/// Decides what to do about a PR whose reviewer hasn't commented or reviewed
+/// in `inactive_days` days, and was or wasn't pinged since.
+fn stale_action(stale: &StaleReviewConfig, inactive_days: i64, pinged: bool) -> StaleAction {
+    if !pinged {
+        if inactive_days >= i64::from(stale.ping_after_days) {
+            return StaleAction::Ping;
+        }
+        return StaleAction::Nothing;
+    }
+    match stale.reassign_after_days {
+        Some(days) if inactive_days >= i64::from(days) => StaleAction::Reassign,
+        _ => StaleAction::Nothing,
+    }
+}
+
+impl StaleReviewState {
+    /// A ping only counts if the reviewer hasn't done anything since.
+    fn pinged(&self, reviewer: &str, last_activity: DateTime<Utc>) -> bool {
+        self.pinged_reviewer
+            .as_ref()
+            .is_some_and(|pinged| pinged.eq_ignore_ascii_case(reviewer))
+            && self.pinged_at.is_some_and(|at| at > last_activity)
+    }
+}
+
+async fn check_pr(
+    ctx: &Context,
+    repo: &Repository,
+    config: &AssignConfig,
+    stale: &StaleReviewConfig,
+    number: u64,
+    recorded: &[OpenAssignment],
+) -> anyhow::Result<()> {
+    let issue = repo.get_pr(&ctx.github, number).await?;
+    if !issue.is_open() || issue.draft || !issue.labels.iter().any(|l| l.name == stale.label) {
+        return Ok(());
+    }
+    // It isn't clear whose review is stale when there are several.
+    let [reviewer] = reviewers(config.review_mode, &issue) else {
+        return Ok(());
+    };
+    let reviewer = &reviewer.login;
+    let assigned_at = match recorded
+        .iter()
+        .find(|a| a.reviewer.eq_ignore_ascii_case(reviewer))
+    {
+        Some(assignment) => assignment.assigned_at,
+        None => issue
+            .assigned_at(&ctx.github, reviewer)
+            .await?
+            .unwrap_or(issue.created_at),
+    };
+
+    let last_activity = last_activity(ctx, &issue, reviewer, assigned_at).await?;
+    let now = Utc::now();
+    let inactive_days = (now - last_activity).num_days();
+
+    let mut client = ctx.db.get().await;
+    let mut state: IssueData<'_, StaleReviewState> =
+        IssueData::load(&mut client, &issue, STALE_REVIEW_KEY).await?;
+    let pinged = state.data.pinged(reviewer, last_activity);
+
+    match stale_action(stale, inactive_days, pinged) {
+        StaleAction::Nothing => Ok(()),
+        StaleAction::Ping => {
+            let message = PING_MESSAGE
+                .replace("{reviewer}", reviewer)
+                .replace("{days}", &inactive_days.to_string());
+            issue.post_comment(&ctx.github, &message).await?;
+            state.data.pinged_reviewer = Some(reviewer.clone());
+            state.data.pinged_at = Some(now);
+            state.save().await?;
+            Ok(())
+        }
+        StaleAction::Reassign => {
+            let Some(new_reviewer) = find_new_reviewer(ctx, config, &issue).await? else {
+                log::info!(
+                    "no other reviewer found to replace {reviewer} on {}",
+                    issue.global_id()
+                );
+                return Ok(());
+            };
+            let message = REASSIGN_MESSAGE
+                .replace("{reviewer}", reviewer)
+                .replace("{days}", &inactive_days.to_string())
+                .replace("{new_reviewer}", &new_reviewer);
+            issue.post_comment(&ctx.github, &message).await?;
+            set_reviewers(
+                &issue,
+                &ctx.github,
+                config.review_mode,
+                std::slice::from_ref(&new_reviewer),
+            )
+            .await;
+            state.data.pinged_reviewer = None;
+            state.data.pinged_at = None;
+            state.save().await?;
+            Ok(())
+        }
+    }
+}
+
+/// Returns when the reviewer last commented on or reviewed the PR, or when
+/// they were assigned if they haven't done either since.
+async fn last_activity(
+    ctx: &Context,
+    issue: &Issue,
+    reviewer: &str,
+    assigned_at: DateTime<Utc>,
+) -> anyhow::Result<DateTime<Utc>> {
+    let comments = issue.comments_since(&ctx.github, assigned_at).await?;
+    let reviews = issue.reviews(&ctx.github).await?;
+    Ok(comments
+        .iter()
+        .chain(&reviews)
+        .filter(|comment| comment.user.login.eq_ignore_ascii_case(reviewer))
+        .map(|comment| comment.updated_at)
+        .fold(assigned_at, std::cmp::max))
+}
+
+/// Picks another reviewer the same way as for a new PR: from the owners of
+/// the modified files, or else from the `fallback` group. The current
+/// assignee is never picked.
+async fn find_new_reviewer(
+    ctx: &Context,
+    config: &AssignConfig,
+    issue: &Issue,
+) -> anyhow::Result<Option<String>> {
+    let teams = crate::team_data::teams(&ctx.github).await?;
+    let mut groups = Vec::new();
+    if let Some(diff) = PullRequestData::new(issue).diff(&ctx.github).await? {
+        let codeowners = load_codeowners(ctx, config, issue).await;
+        let (candidates, _) = find_reviewers_from_diff(
+            config,
+            codeowners.as_ref(),
+            diff,
+            &mut Explanation::disabled(),
+        )?;
+        if !candidates.is_empty() {
+            groups.push(candidates);
+        }
+    }
+    if let Some(fallback) = config.adhoc_groups.get("fallback") {
+        groups.push(fallback.clone());
+    }
+    for names in groups {
+        match find_reviewers_from_names(ctx, &teams, config, issue, &names, 1, &[]).await {
+            Ok(mut reviewers) => return Ok(reviewers.pop()),
+            Err(e) => log::trace!(
+                "failed to find a new reviewer for {}: {e}",
+                issue.global_id()
+            ),
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn config(reassign_after_days: Option<u32>) -> StaleReviewConfig {
+        StaleReviewConfig {
+            ping_after_days: 7,
+            reassign_after_days,
+            label: "S-waiting-on-review".to_string(),
+        }
+    }
+
+    #[test]
+    fn ping_threshold() {
+        let stale = config(Some(14));
+        assert_eq!(stale_action(&stale, 6, false), StaleAction::Nothing);
+        assert_eq!(stale_action(&stale, 7, false), StaleAction::Ping);
+        // Reviewers are pinged before being reassigned, however long they
+        // have been inactive.
+        assert_eq!(stale_action(&stale, 30, false), StaleAction::Ping);
+    }
+
+    #[test]
+    fn reassign_threshold() {
+        let stale = config(Some(14));
+        assert_eq!(stale_action(&stale, 7, true), StaleAction::Nothing);
+        assert_eq!(stale_action(&stale, 13, true), StaleAction::Nothing);
+        assert_eq!(stale_action(&stale, 14, true), StaleAction::Reassign);
+        assert_eq!(stale_action(&config(None), 100, true), StaleAction::Nothing);
+    }
+
+    #[test]
+    fn ping_counts_until_activity() {
+        let pinged_at = Utc.ymd(2023, 1, 10).and_hms(0, 0, 0);
+        let state = StaleReviewState {
+            pinged_reviewer: Some("Alice".to_string()),
+            pinged_at: Some(pinged_at),
+        };
+        assert!(state.pinged("alice", Utc.ymd(2023, 1, 1).and_hms(0, 0, 0)));
+        assert!(!state.pinged("alice", Utc.ymd(2023, 1, 11).and_hms(0, 0, 0)));
+        assert!(!state.pinged("bob", Utc.ymd(2023, 1, 1).and_hms(0, 0, 0)));
+        assert!(!StaleReviewState::default().pinged("alice", pinged_at));
+    }
+}
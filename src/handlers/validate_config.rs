@@ -0,0 +1,427 @@
+//! Purpose: When a PR modifies `triagebot.toml`, check that the new
+//! configuration is valid, and post a comment listing any problems.
+//!
+//! The same checks are available for local use with the `/validate-config`
+//! endpoint.
+
+use crate::{
+    config::{self, AssignConfig, Config, CONFIG_FILE_NAME},
+    db::issue_data::IssueData,
+    github::{
+        files_changed, GithubClient, IssueRepository, IssuesAction, IssuesEvent, PullRequestData,
+    },
+    handlers::Context,
+};
+use anyhow::Context as _;
+use rust_team_data::v1::Teams;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use tracing as log;
+
+const VALIDATE_CONFIG_KEY: &str = "validate_config";
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct ValidateConfigState {
+    /// The problems reported in the last comment, to avoid posting the same
+    /// comment for every push.
+    problems: Vec<String>,
+}
+
+pub(super) async fn handle(
+    ctx: &Context,
+    event: &IssuesEvent,
+    pr: &PullRequestData<'_>,
+) -> anyhow::Result<()> {
+    if !event.issue.is_pr()
+        || !matches!(
+            event.action,
+            IssuesAction::Opened | IssuesAction::Synchronize | IssuesAction::Reopened
+        )
+    {
+        return Ok(());
+    }
+    let Some(diff) = pr.diff(&ctx.github).await? else {
+        return Ok(());
+    };
+    if !files_changed(diff).contains(&CONFIG_FILE_NAME) {
+        return Ok(());
+    }
+    let head = event.issue.head.as_ref().context("PR has no head")?;
+
+    let problems = match ctx
+        .github
+        .raw_file(&head.repo.full_name, &head.sha, CONFIG_FILE_NAME)
+        .await?
+    {
+        Some(contents) => validate(&ctx.github, Some(event.issue.repository()), &contents).await?,
+        // The file was deleted.
+        None => Vec::new(),
+    };
+
+    let mut client = ctx.db.get().await;
+    let mut state: IssueData<'_, ValidateConfigState> =
+        IssueData::load(&mut client, &event.issue, VALIDATE_CONFIG_KEY).await?;
+    if state.data.problems == problems {
+        return Ok(());
+    }
+    let message = if problems.is_empty() {
+        format!("The problems with `{CONFIG_FILE_NAME}` have been fixed.")
+    } else {
+        let mut message =
+            format!("The `{CONFIG_FILE_NAME}` in this pull request has some problems:\n");
+        for problem in &problems {
+            write!(message, "\n- {}", problem.replace('\n', "\n  ")).unwrap();
+        }
+        message
+    };
+    event
+        .issue
+        .post_comment(&ctx.github, &message)
+        .await
+        .context("failed to post config validation comment")?;
+    state.data.problems = problems;
+    state.save().await?;
+    Ok(())
+}
+
+/// Checks the contents of a `triagebot.toml`, returning a list of problems.
+///
+/// If `repo` is given, labels used by the configuration are checked to exist
+/// in that repository.
+///
+/// Only returns an error if the data needed for the checks couldn't be
+/// fetched.
+pub async fn validate(
+    gh: &GithubClient,
+    repo: Option<&IssueRepository>,
+    contents: &[u8],
+) -> anyhow::Result<Vec<String>> {
+    let config = match config::parse_config(contents) {
+        Ok(config) => config,
+        Err(e) => {
+            return Ok(vec![format!(
+                "invalid configuration: {}",
+                config::describe_toml_error(&e)
+            )])
+        }
+    };
+    let mut problems = check_patterns(&config);
+
+    let teams = crate::team_data::teams(gh).await?;
+    problems.extend(check_teams(&config, &teams));
+
+    if let Some(repo) = repo {
+        for (section, label) in labels(&config) {
+            // Labels with wildcards are patterns, not names.
+            if label.contains('*') {
+                continue;
+            }
+            if !repo.has_label(gh, label).await? {
+                problems.push(format!(
+                    "`{section}`: label `{label}` does not exist in {repo}"
+                ));
+            }
+        }
+    }
+    log::debug!("validated config with {} problems", problems.len());
+    Ok(problems)
+}
+
+/// Checks that the paths in `assign.owners` and label globs in `autolabel`
+/// can be parsed.
+fn check_patterns(config: &Config) -> Vec<String> {
+    let mut problems = Vec::new();
+    if let Some(assign) = &config.assign {
+        let mut owners: Vec<_> = assign.owners.keys().collect();
+        owners.sort();
+        for pattern in owners {
+            if let Err(e) = ignore::gitignore::GitignoreBuilder::new("/").add_line(None, pattern) {
+                problems.push(format!(
+                    "`assign.owners`: `{pattern}` is not a valid gitignore pattern: {e}"
+                ));
+            }
+        }
+    }
+    if let Some(autolabel) = &config.autolabel {
+        let mut labels: Vec<_> = autolabel.labels.iter().collect();
+        labels.sort_by_key(|(label, _)| *label);
+        for (label, cfg) in labels {
+            for pattern in &cfg.exclude_labels {
+                if let Err(e) = glob::Pattern::new(pattern) {
+                    problems.push(format!(
+                        "`autolabel.\"{label}\".exclude_labels`: `{pattern}` is not a valid glob: {e}"
+                    ));
+                }
+            }
+        }
+    }
+    problems
+}
+
+/// Checks that the teams and groups referred to by `assign` and `ping`
+/// exist.
+fn check_teams(config: &Config, teams: &Teams) -> Vec<String> {
+    let mut problems = Vec::new();
+    if let Some(assign) = &config.assign {
+        let mut names: Vec<(String, &String)> = Vec::new();
+        for (path, owners) in &assign.owners {
+            names.extend(
+                owners
+                    .reviewers()
+                    .iter()
+                    .map(|o| (format!("assign.owners.\"{path}\""), o)),
+            );
+        }
+        for (group, members) in &assign.adhoc_groups {
+            names.extend(
+                members
+                    .iter()
+                    .map(|m| (format!("assign.adhoc_groups.{group}"), m)),
+            );
+        }
+        names.sort();
+        for (section, name) in names {
+            // Mirrors the lookup in `assign::candidate_reviewers_from_names`:
+            // names without a slash that aren't groups or teams are assumed to
+            // be usernames.
+            let name = name.strip_prefix('@').unwrap_or(name);
+            let Some((org, group)) = name.split_once('/') else {
+                if let Some(problem) = check_bare_name(name, assign, teams) {
+                    problems.push(format!("`{section}`: {problem}"));
+                }
+                continue;
+            };
+            let is_group = assign.adhoc_groups.contains_key(group);
+            let is_team = org == "rust-lang" && teams.teams.contains_key(group);
+            if !is_group && !is_team {
+                problems.push(format!(
+                    "`{section}`: `{name}` is not a known team or ad-hoc group"
+                ));
+            }
+        }
+    }
+    if let Some(ping) = &config.ping {
+        let mut names: Vec<_> = ping.teams.keys().collect();
+        names.sort();
+        for name in names {
+            if !teams.teams.contains_key(name) {
+                problems.push(format!("`ping.{name}`: `{name}` is not a known team"));
+            }
+        }
+    }
+    problems
+}
+
+/// Checks a name without a slash in `assign`, which is taken as a username
+/// if it isn't an ad-hoc group or team. That is a problem if it can't be a
+/// GitHub username, or if it looks like a misspelled team or group and isn't
+/// a member of any team.
+fn check_bare_name(name: &str, assign: &AssignConfig, teams: &Teams) -> Option<String> {
+    if assign.adhoc_groups.contains_key(name) || teams.teams.contains_key(name) {
+        return None;
+    }
+    if !is_github_login(name) {
+        return Some(format!(
+            "`{name}` is not a known team or ad-hoc group, nor a valid GitHub username"
+        ));
+    }
+    let is_member = teams.teams.values().any(|team| {
+        team.members
+            .iter()
+            .any(|m| m.github.eq_ignore_ascii_case(name))
+    });
+    if is_member {
+        return None;
+    }
+    let (distance, closest) = teams
+        .teams
+        .keys()
+        .chain(assign.adhoc_groups.keys())
+        .map(|group| (config::edit_distance(name, group), group))
+        .min()?;
+    (distance <= std::cmp::max(1, name.chars().count() / 3)).then(|| {
+        format!(
+            "`{name}` is not a known team or ad-hoc group, so it is taken as a username. \
+             Did you mean `{closest}`?"
+        )
+    })
+}
+
+/// Whether `name` can be a GitHub username, which is made of ASCII letters,
+/// digits and hyphens, and is at most 39 characters long.
+fn is_github_login(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 39
+        && !name.starts_with('-')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// Returns the labels referred to by the configuration, along with the
+/// section they are used in.
+fn labels(config: &Config) -> Vec<(String, &str)> {
+    let mut labels = Vec::new();
+    if let Some(ping) = &config.ping {
+        for (team, cfg) in &ping.teams {
+            if let Some(label) = &cfg.label {
+                labels.push((format!("ping.{team}.label"), label.as_str()));
+            }
+        }
+    }
+    if let Some(nominate) = &config.nominate {
+        for (team, label) in &nominate.teams {
+            labels.push((format!("nominate.teams.{team}"), label.as_str()));
+        }
+    }
+    if let Some(prioritize) = &config.prioritize {
+        labels.push(("prioritize.label".to_string(), &prioritize.label));
+    }
+    if let Some(major_change) = &config.major_change {
+        for (field, label) in [
+            ("enabling_label", &major_change.enabling_label),
+            ("second_label", &major_change.second_label),
+            ("accept_label", &major_change.accept_label),
+            ("meeting_label", &major_change.meeting_label),
+        ] {
+            labels.push((format!("major-change.{field}"), label.as_str()));
+        }
+    }
+    if let Some(autolabel) = &config.autolabel {
+        for label in autolabel.labels.keys() {
+            labels.push((format!("autolabel.\"{label}\""), label.as_str()));
+        }
+    }
+    if let Some(notify_zulip) = &config.notify_zulip {
+        for label in notify_zulip.labels.keys() {
+            labels.push((format!("notify-zulip.\"{label}\""), label.as_str()));
+        }
+    }
+    if let Some(review_submitted) = &config.review_submitted {
+        for label in &review_submitted.review_labels {
+            labels.push(("review-submitted.review_labels".to_string(), label.as_str()));
+        }
+        labels.push((
+            "review-submitted.reviewed_label".to_string(),
+            &review_submitted.reviewed_label,
+        ));
+    }
+    if let Some(shortcut) = &config.shortcut {
+        for (name, cfg) in shortcut.shortcuts() {
+            labels.push((format!("shortcut.{name}.label"), cfg.label.as_str()));
+        }
+    }
+    if let Some(no_merges) = &config.no_merges {
+        for label in &no_merges.labels {
+            labels.push(("no-merges.labels".to_string(), label.as_str()));
+        }
+    }
+    labels.sort();
+    labels.dedup();
+    labels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(config: &str) -> Config {
+        config::parse_config(config.as_bytes()).unwrap()
+    }
+
+    fn teams() -> Teams {
+        serde_json::from_value(serde_json::json!({
+            "compiler": {
+                "name": "compiler",
+                "kind": "team",
+                "members": [
+                    {"name": "Member", "github": "compilers", "github_id": 1, "is_lead": false},
+                ],
+                "alumni": [],
+                "discord": [],
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn invalid_patterns() {
+        let config = parse(
+            r#"
+            [assign.owners]
+            "/compiler" = ["compiler"]
+            "/library/{core" = ["libs"]
+
+            [autolabel."T-compiler"]
+            exclude_labels = ["T-*", "A-[diagnostics"]
+            "#,
+        );
+        let problems = check_patterns(&config);
+        assert_eq!(problems.len(), 2, "{problems:?}");
+        assert!(problems[0].starts_with("`assign.owners`: `/library/{core`"));
+        assert!(
+            problems[1].starts_with("`autolabel.\"T-compiler\".exclude_labels`: `A-[diagnostics`")
+        );
+    }
+
+    #[test]
+    fn unknown_teams() {
+        let config = parse(
+            r#"
+            [assign.adhoc_groups]
+            fallback = ["@octocat", "rust-lang/compiler", "rust-lang/libs"]
+
+            [assign.owners]
+            "/compiler" = ["compiler", "rust-lang/fallback", "other-org/compiler"]
+            "/library" = ["compilr", "fallbak", "compiler_team", "@compilers"]
+
+            [ping.compiler]
+            message = "hi"
+
+            [ping.wg-nonexistent]
+            message = "hi"
+            "#,
+        );
+        assert_eq!(
+            check_teams(&config, &teams()),
+            [
+                "`assign.adhoc_groups.fallback`: `rust-lang/libs` is not a known team or ad-hoc group",
+                "`assign.owners.\"/compiler\"`: `other-org/compiler` is not a known team or ad-hoc group",
+                "`assign.owners.\"/library\"`: `compiler_team` is not a known team or ad-hoc group, nor a valid GitHub username",
+                "`assign.owners.\"/library\"`: `compilr` is not a known team or ad-hoc group, so it is taken as a username. Did you mean `compiler`?",
+                "`assign.owners.\"/library\"`: `fallbak` is not a known team or ad-hoc group, so it is taken as a username. Did you mean `fallback`?",
+                "`ping.wg-nonexistent`: `wg-nonexistent` is not a known team",
+            ]
+        );
+    }
+
+    #[test]
+    fn referenced_labels() {
+        let config = parse(
+            r#"
+            [prioritize]
+            label = "I-prioritize"
+
+            [autolabel."A-diagnostics"]
+            trigger_files = ["compiler/rustc_errors"]
+
+            [review-submitted]
+            review_labels = ["S-waiting-on-review"]
+            reviewed_label = "S-waiting-on-author"
+            "#,
+        );
+        assert_eq!(
+            labels(&config),
+            [
+                ("autolabel.\"A-diagnostics\"".to_string(), "A-diagnostics"),
+                ("prioritize.label".to_string(), "I-prioritize"),
+                (
+                    "review-submitted.review_labels".to_string(),
+                    "S-waiting-on-review"
+                ),
+                (
+                    "review-submitted.reviewed_label".to_string(),
+                    "S-waiting-on-author"
+                ),
+            ]
+        );
+    }
+}
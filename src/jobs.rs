@@ -1,39 +1,69 @@
 //! SCHEDULED JOBS
 //!
-//! The metadata is a serde_json::Value
-//! Please refer to https://docs.rs/serde_json/latest/serde_json/value/fn.from_value.html
-//! on how to interpret it as an instance of type T, implementing Serialize/Deserialize.
+//! Every kind of job is implemented by a `JobHandler`, registered in `registry()` in
+//! src/handlers/jobs.rs. The handler's `Metadata` is stored as JSON in the `jobs` table, and
+//! given back to the handler when the job runs.
 //!
-//! The schedule is a cron::Schedule
-//! Please refer to https://docs.rs/cron/latest/cron/struct.Schedule.html for further info
+//! For example, to send a Zulip message into #t-release:
 //!
-//! For example, if we want to sends a Zulip message every Friday at 11:30am ET into #t-release
-//! with a @T-release meeting! content, we should create some JobSchedule like:
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! pub struct ZulipMetadata {
+//!     pub message: String,
+//! }
 //!
-//!    #[derive(Serialize, Deserialize)]
-//!    struct ZulipMetadata {
-//!      pub message: String
-//!    }
+//! pub struct ZulipMessageJob;
 //!
-//!    let metadata = serde_json::value::to_value(ZulipMetadata {
-//!      message: "@T-release meeting!".to_string()
-//!    }).unwrap();
+//! #[async_trait]
+//! impl JobHandler for ZulipMessageJob {
+//!     const NAME: &'static str = "send_zulip_message";
+//!     type Metadata = ZulipMetadata;
 //!
-//!    let schedule = Schedule::from_str("0 30 11 * * FRI *").unwrap();
-//!    
-//!    let new_job = JobSchedule {
-//!      name: "send_zulip_message".to_owned(),
-//!      schedule: schedule,
-//!      metadata: metadata
-//!    }
+//!     async fn run(&self, ctx: &Context, metadata: ZulipMetadata) -> anyhow::Result<()> {
+//!         // ...
+//!     }
+//! }
+//! ```
+//!
+//! A handler can then run it once, for example in ten days, with `schedule_job`:
+//!
+//! ```ignore
+//! let metadata = ZulipMetadata {
+//!     message: "@T-release meeting!".to_string(),
+//! };
+//! schedule_job::<ZulipMessageJob>(&db, Utc::now() + Duration::days(10), &metadata).await?;
+//! ```
+//!
+//! To run it every Friday at 11:30am ET instead, create a JobSchedule (the schedule is a
+//! cron::Schedule, please refer to https://docs.rs/cron/latest/cron/struct.Schedule.html for
+//! further info):
+//!
+//! ```ignore
+//! let new_job = JobSchedule {
+//!     name: ZulipMessageJob::NAME.to_owned(),
+//!     schedule: Schedule::from_str("0 30 11 * * FRI *").unwrap(),
+//!     metadata: serde_json::to_value(metadata).unwrap(),
+//! };
+//! ```
 //!
 //! and include it in the below vector in jobs():
 //!
 //!   jobs.push(new_job);
 //!
-//! ... fianlly, add the corresponding "send_zulip_message" handler in src/handlers/jobs.rs
+//! A job that fails is retried according to the `RetryPolicy` of its handler, and is marked as
+//! dead once it has failed `max_attempts` times. The queue and the recent executions are listed
+//! on `/jobs`.
 
-use crate::db::jobs::JobSchedule;
+use crate::db::jobs::{
+    get_jobs, get_recent_executions, insert_job, Job, JobExecution, JobSchedule, JobState,
+    RetryPolicy,
+};
+use crate::handlers::Context;
+use anyhow::Context as _;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokio_postgres::Client as DbClient;
 
 // How often new cron-based jobs will be placed in the queue.
 // This is the minimum period *between* a single cron task's executions.
@@ -48,12 +78,214 @@ pub fn jobs() -> Vec<JobSchedule> {
     let mut jobs: Vec<JobSchedule> = Vec::new();
     jobs.push(crate::handlers::docs_update::job());
     jobs.push(crate::handlers::rustc_commits::job());
+    jobs.push(crate::handlers::stale_reviews::job());
 
     jobs
 }
 
+/// Runs a kind of job.
+#[async_trait]
+pub trait JobHandler: Send + Sync {
+    /// The name of the job in the `jobs` table.
+    const NAME: &'static str;
+    /// The data the job needs to run.
+    type Metadata: Serialize + DeserializeOwned + Send;
+
+    fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy::default()
+    }
+
+    async fn run(&self, ctx: &Context, metadata: Self::Metadata) -> anyhow::Result<()>;
+}
+
+/// A [`JobHandler`] whose metadata hasn't been deserialized yet, so that
+/// handlers of different jobs can be stored in the registry.
+#[async_trait]
+pub trait DynJobHandler: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn retry_policy(&self) -> RetryPolicy;
+
+    async fn run(&self, ctx: &Context, metadata: &serde_json::Value) -> anyhow::Result<()>;
+}
+
+#[async_trait]
+impl<J: JobHandler> DynJobHandler for J {
+    fn name(&self) -> &'static str {
+        J::NAME
+    }
+
+    fn retry_policy(&self) -> RetryPolicy {
+        JobHandler::retry_policy(self)
+    }
+
+    async fn run(&self, ctx: &Context, metadata: &serde_json::Value) -> anyhow::Result<()> {
+        let metadata = J::Metadata::deserialize(metadata)
+            .with_context(|| format!("invalid metadata for job {}", J::NAME))?;
+        JobHandler::run(self, ctx, metadata).await
+    }
+}
+
+/// Returns the handler of the job with the given name.
+pub fn handler(name: &str) -> Option<Box<dyn DynJobHandler>> {
+    crate::handlers::jobs::registry()
+        .into_iter()
+        .find(|handler| handler.name() == name)
+}
+
+/// Returns the retry policy of the job with the given name. Jobs without a
+/// handler aren't retried, since they would fail again.
+pub fn retry_policy(name: &str) -> RetryPolicy {
+    handler(name).map_or_else(RetryPolicy::never, |handler| handler.retry_policy())
+}
+
+/// Schedules a single run of the job `J` at `run_at`.
+///
+/// Scheduling a job that is already scheduled at the same time with the same
+/// metadata does nothing.
+pub async fn schedule_job<J: JobHandler>(
+    db: &DbClient,
+    run_at: DateTime<Utc>,
+    metadata: &J::Metadata,
+) -> anyhow::Result<()> {
+    let metadata = serde_json::to_value(metadata)
+        .with_context(|| format!("serializing metadata of job {}", J::NAME))?;
+    insert_job(db, J::NAME, &run_at, &metadata).await
+}
+
+/// The number of executions listed on the `/jobs` page.
+const LISTED_EXECUTIONS: i64 = 100;
+
+/// Renders the `/jobs` page, listing the jobs in the queue and the latest
+/// executions.
+pub async fn render(db: &crate::db::PooledClient) -> String {
+    let (jobs, executions) = match (
+        get_jobs(db).await,
+        get_recent_executions(db, LISTED_EXECUTIONS).await,
+    ) {
+        (Ok(jobs), Ok(executions)) => (jobs, executions),
+        (Err(e), _) | (_, Err(e)) => return format!("{:?}", e.context("getting jobs")),
+    };
+
+    let mut out = String::new();
+    out.push_str("<html>");
+    out.push_str("<head>");
+    out.push_str("<meta charset=\"utf-8\">");
+    out.push_str("<title>Triagebot Jobs</title>");
+    out.push_str("</head>");
+    out.push_str("<body>");
+
+    let running: Vec<&Job> = jobs
+        .iter()
+        .filter(|job| job.state == JobState::Running)
+        .collect();
+    let upcoming: Vec<&Job> = jobs
+        .iter()
+        .filter(|job| job.state == JobState::Pending)
+        .collect();
+    let dead: Vec<&Job> = jobs
+        .iter()
+        .filter(|job| job.state == JobState::Dead)
+        .collect();
+    render_jobs(&mut out, "Running", &running);
+    render_jobs(&mut out, "Upcoming", &upcoming);
+    render_jobs(&mut out, "Dead (failed too many times)", &dead);
+
+    let (failed, completed): (Vec<&JobExecution>, Vec<&JobExecution>) = executions
+        .iter()
+        .filter(|execution| execution.finished_at.is_some())
+        .partition(|execution| execution.error_message.is_some());
+    render_executions(&mut out, "Failed", &failed);
+    render_executions(&mut out, "Completed", &completed);
+
+    out.push_str("</body>");
+    out.push_str("</html>");
+    out
+}
+
+fn render_jobs(out: &mut String, title: &str, jobs: &[&Job]) {
+    out.push_str(&format!("<h3>{title}</h3>"));
+    if jobs.is_empty() {
+        out.push_str("<p><em>None.</em></p>");
+        return;
+    }
+    out.push_str("<table>");
+    out.push_str(
+        "<tr><th>Name</th><th>Scheduled at</th><th>Attempts</th>\
+         <th>Last started at</th><th>Next retry at</th><th>Last error</th></tr>",
+    );
+    for job in jobs {
+        out.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td><pre>{}</pre></td></tr>",
+            escape(&job.name),
+            format_time(Some(job.scheduled_at)),
+            job.attempts,
+            format_time(job.executed_at),
+            format_time(job.retry_at),
+            escape(job.error_message.as_deref().unwrap_or_default()),
+        ));
+    }
+    out.push_str("</table>");
+}
+
+fn render_executions(out: &mut String, title: &str, executions: &[&JobExecution]) {
+    out.push_str(&format!("<h3>{title}</h3>"));
+    if executions.is_empty() {
+        out.push_str("<p><em>None.</em></p>");
+        return;
+    }
+    out.push_str("<table>");
+    out.push_str(
+        "<tr><th>Name</th><th>Scheduled at</th><th>Attempt</th>\
+         <th>Started at</th><th>Duration</th><th>Error</th></tr>",
+    );
+    for execution in executions {
+        let duration = execution.duration().map_or_else(String::new, |duration| {
+            format!("{:.1}s", duration.num_milliseconds() as f64 / 1000.0)
+        });
+        out.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td><pre>{}</pre></td></tr>",
+            escape(&execution.name),
+            format_time(Some(execution.scheduled_at)),
+            execution.attempt,
+            format_time(Some(execution.started_at)),
+            duration,
+            escape(execution.error_message.as_deref().unwrap_or_default()),
+        ));
+    }
+    out.push_str("</table>");
+}
+
+fn format_time(time: Option<DateTime<Utc>>) -> String {
+    time.map_or_else(String::new, |time| {
+        time.format("%Y-%m-%d %H:%M:%S UTC").to_string()
+    })
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
 #[test]
 fn jobs_defined() {
     // Checks we don't panic here, mostly for the schedule parsing.
-    drop(jobs());
+    for job in jobs() {
+        assert!(handler(&job.name).is_some(), "no handler for {}", job.name);
+    }
+}
+
+#[test]
+fn handler_names_are_unique() {
+    let mut names: Vec<_> = crate::handlers::jobs::registry()
+        .iter()
+        .map(|handler| handler.name())
+        .collect();
+    let count = names.len();
+    names.sort();
+    names.dedup();
+    assert_eq!(names.len(), count);
 }
@@ -16,6 +16,7 @@ pub mod agenda;
 mod changelogs;
 pub mod config;
 pub mod db;
+pub mod dry_run;
 pub mod github;
 pub mod handlers;
 pub mod http_client;
@@ -26,6 +27,7 @@ pub mod payload;
 pub mod rfcbot;
 pub mod team;
 mod team_data;
+pub mod test_record;
 pub mod triage;
 pub mod zulip;
 
@@ -231,7 +233,7 @@ pub async fn webhook(
         }
     };
     let errors = handlers::handle(&ctx, &event).await;
-    let mut other_error = false;
+    let mut other_errors = Vec::new();
     let mut message = String::new();
     for err in errors {
         match err {
@@ -243,7 +245,7 @@ pub async fn webhook(
             }
             HandlerError::Other(err) => {
                 log::error!("handling event failed: {:?}", err);
-                other_error = true;
+                other_errors.push(format!("{:?}", err));
             }
         }
     }
@@ -253,11 +255,82 @@ pub async fn webhook(
             cmnt.post(&ctx.github).await?;
         }
     }
-    if other_error {
+    if !other_errors.is_empty() {
         Err(WebhookError(anyhow::anyhow!(
-            "handling failed, error logged",
+            "handling failed, error logged:\n\n{}",
+            other_errors.join("\n\n")
         )))
     } else {
         Ok(true)
     }
 }
+
+/// Handles a webhook delivery like [`webhook`], additionally storing the
+/// delivery and any handler errors in the `github_events` table so that it
+/// can be replayed with [`replay_webhook`].
+///
+/// Failing to write to the event log does not prevent the event from being
+/// handled.
+pub async fn logged_webhook(
+    event: EventName,
+    delivery_id: &str,
+    payload: String,
+    ctx: &handlers::Context,
+) -> Result<bool, WebhookError> {
+    if matches!(event, EventName::Other) {
+        return Ok(false);
+    }
+    if let Err(e) = db::events::record_event(
+        &*ctx.db.get().await,
+        delivery_id,
+        &event.to_string(),
+        &payload,
+    )
+    .await
+    {
+        log::error!("failed to record event {}: {:?}", delivery_id, e);
+    }
+    let res = webhook(event, payload, ctx).await;
+    record_webhook_result(ctx, delivery_id, &res).await;
+    res
+}
+
+/// Runs a delivery previously stored by [`logged_webhook`] through the
+/// handlers again.
+///
+/// The stored error message is replaced with the outcome of the replay.
+pub async fn replay_webhook(delivery_id: &str, ctx: &handlers::Context) -> anyhow::Result<bool> {
+    let stored = db::events::get_event(&*ctx.db.get().await, delivery_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("no stored event with delivery id `{}`", delivery_id))?;
+    log::info!(
+        "replaying {} event {} received at {}",
+        stored.event,
+        delivery_id,
+        stored.received_at
+    );
+    let event = stored.event.parse::<EventName>().unwrap();
+    let res = webhook(event, stored.payload, ctx).await;
+    if let Err(e) = db::events::update_event_replayed_at(&*ctx.db.get().await, delivery_id).await {
+        log::error!("failed to update replay time of {}: {:?}", delivery_id, e);
+    }
+    record_webhook_result(ctx, delivery_id, &res).await;
+    res.map_err(|e| e.0)
+}
+
+async fn record_webhook_result(
+    ctx: &handlers::Context,
+    delivery_id: &str,
+    res: &Result<bool, WebhookError>,
+) {
+    let message = res.as_ref().err().map(|e| format!("{:?}", e.0));
+    if let Err(e) = db::events::update_event_error_message(
+        &*ctx.db.get().await,
+        delivery_id,
+        message.as_deref(),
+    )
+    .await
+    {
+        log::error!("failed to record result of event {}: {:?}", delivery_id, e);
+    }
+}
@@ -34,6 +34,7 @@ async fn serve_req(
     let mut router = Router::new();
     router.add("/triage", "index".to_string());
     router.add("/triage/:owner/:repo", "pulls".to_string());
+    router.add("/reviewers/:owner/:repo", "reviewers".to_string());
     let (req, body_stream) = req.into_parts();
 
     if let Ok(matcher) = router.recognize(req.uri.path()) {
@@ -42,6 +43,15 @@ async fn serve_req(
             let owner = params.find("owner");
             let repo = params.find("repo");
             return triagebot::triage::pulls(ctx, owner.unwrap(), repo.unwrap()).await;
+        } else if matcher.handler().as_str() == "reviewers" {
+            let params = matcher.params();
+            let owner = params.find("owner");
+            let repo = params.find("repo");
+            let json = req.uri.query().is_some_and(|query| {
+                url::form_urlencoded::parse(query.as_bytes())
+                    .any(|(k, v)| k == "format" && v == "json")
+            });
+            return triagebot::triage::reviewers(ctx, owner.unwrap(), repo.unwrap(), json).await;
         } else {
             return triagebot::triage::index();
         }
@@ -119,6 +129,67 @@ async fn serve_req(
             )))
             .unwrap());
     }
+    if req.uri.path() == "/jobs" {
+        // The errors of failed jobs can include responses from GitHub.
+        if let Some(response) = check_replay_token(&req) {
+            return Ok(response);
+        }
+        return Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header("Content-Type", "text/html; charset=utf-8")
+            .body(Body::from(
+                triagebot::jobs::render(&ctx.db.get().await).await,
+            ))
+            .unwrap());
+    }
+    if req.uri.path() == "/dry-run" {
+        let repo = req.uri.query().and_then(|query| {
+            url::form_urlencoded::parse(query.as_bytes())
+                .find(|(k, _)| k == "repo")
+                .map(|(_, repo)| repo.into_owned())
+        });
+        return Ok(Response::builder()
+            .status(StatusCode::OK)
+            .body(Body::from(triagebot::dry_run::render(repo.as_deref())))
+            .unwrap());
+    }
+    if req.uri.path() == "/cached-configs" {
+        return Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header("Content-Type", "application/json")
+            .body(Body::from(
+                serde_json::to_string(&triagebot::config::cached_configs()).unwrap(),
+            ))
+            .unwrap());
+    }
+    if req.uri.path() == "/validate-config" {
+        if req.method != hyper::Method::POST {
+            return Ok(Response::builder()
+                .status(StatusCode::METHOD_NOT_ALLOWED)
+                .header(header::ALLOW, "POST")
+                .body(Body::from(
+                    "POST the contents of a triagebot.toml, optionally with \
+                     `?repo=<owner>/<repo>` and the replay token to check that \
+                     its labels exist.",
+                ))
+                .unwrap());
+        }
+        let mut c = body_stream;
+        let mut payload = Vec::new();
+        while let Some(chunk) = c.next().await {
+            let chunk = chunk?;
+            if payload.len() + chunk.len() > MAX_CONFIG_SIZE {
+                return Ok(Response::builder()
+                    .status(StatusCode::PAYLOAD_TOO_LARGE)
+                    .body(Body::from(format!(
+                        "The configuration must be at most {MAX_CONFIG_SIZE} bytes"
+                    )))
+                    .unwrap());
+            }
+            payload.extend_from_slice(&chunk);
+        }
+        return Ok(validate_config(&req, &payload, &ctx).await);
+    }
     if req.uri.path() == "/zulip-hook" {
         let mut c = body_stream;
         let mut payload = Vec::new();
@@ -145,6 +216,9 @@ async fn serve_req(
             .body(Body::from(triagebot::zulip::respond(&ctx, req).await))
             .unwrap());
     }
+    if req.uri.path() == "/github-hook/replay" {
+        return Ok(replay_event(&req, &ctx).await);
+    }
     if req.uri.path() != "/github-hook" {
         return Ok(Response::builder()
             .status(StatusCode::NOT_FOUND)
@@ -179,20 +253,41 @@ async fn serve_req(
             .unwrap());
     };
     log::debug!("event={}", event);
-    let signature = if let Some(sig) = req.headers.get("X-Hub-Signature") {
+    let delivery_id = match req.headers.get("X-GitHub-Delivery") {
+        Some(id) => match id.to_str().ok() {
+            Some(v) => v.to_string(),
+            None => {
+                return Ok(Response::builder()
+                    .status(StatusCode::BAD_REQUEST)
+                    .body(Body::from("X-GitHub-Delivery header must be UTF-8 encoded"))
+                    .unwrap());
+            }
+        },
+        // Deliveries are always expected to carry an ID, but make up one so
+        // that the event can still be logged and replayed.
+        None => uuid::Uuid::new_v4().to_string(),
+    };
+    log::debug!("delivery_id={}", delivery_id);
+    // Prefer the sha256 signature, falling back to the legacy sha1 one.
+    let signature_header = ["X-Hub-Signature-256", "X-Hub-Signature"]
+        .into_iter()
+        .find_map(|name| Some((name, req.headers.get(name)?)));
+    let signature = if let Some((name, sig)) = signature_header {
         match sig.to_str().ok() {
             Some(v) => v,
             None => {
                 return Ok(Response::builder()
                     .status(StatusCode::BAD_REQUEST)
-                    .body(Body::from("X-Hub-Signature header must be UTF-8 encoded"))
+                    .body(Body::from(format!("{} header must be UTF-8 encoded", name)))
                     .unwrap());
             }
         }
     } else {
         return Ok(Response::builder()
             .status(StatusCode::BAD_REQUEST)
-            .body(Body::from("X-Hub-Signature header must be set"))
+            .body(Body::from(
+                "X-Hub-Signature-256 or X-Hub-Signature header must be set",
+            ))
             .unwrap());
     };
     log::debug!("signature={}", signature);
@@ -204,11 +299,22 @@ async fn serve_req(
         payload.extend_from_slice(&chunk);
     }
 
-    if let Err(_) = payload::assert_signed(signature, &payload) {
-        return Ok(Response::builder()
-            .status(StatusCode::FORBIDDEN)
-            .body(Body::from("Wrong signature"))
-            .unwrap());
+    match payload::assert_signed(signature, &payload) {
+        Ok(()) => {}
+        Err(e @ payload::SignedPayloadError::MissingSecret) => {
+            log::error!("cannot validate webhook signature: {}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Body::from("Webhook secret is not configured"))
+                .unwrap());
+        }
+        Err(e) => {
+            log::debug!("rejecting webhook: {}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::FORBIDDEN)
+                .body(Body::from(format!("Wrong signature: {}", e)))
+                .unwrap());
+        }
     }
     let payload = match String::from_utf8(payload) {
         Ok(p) => p,
@@ -220,19 +326,162 @@ async fn serve_req(
         }
     };
 
-    match triagebot::webhook(event, payload, &ctx).await {
+    triagebot::test_record::record_event(&event.to_string(), &payload);
+
+    match triagebot::logged_webhook(event, &delivery_id, payload, &ctx).await {
         Ok(true) => Ok(Response::new(Body::from("processed request"))),
         Ok(false) => Ok(Response::new(Body::from("ignored request"))),
         Err(err) => {
-            log::error!("request failed: {:?}", err);
+            // The errors may contain responses of the GitHub API, so they are
+            // only logged and stored with the delivery, not sent back.
+            log::error!("request {} failed: {:?}", delivery_id, err);
             Ok(Response::builder()
                 .status(StatusCode::INTERNAL_SERVER_ERROR)
-                .body(Body::from(format!("request failed: {:?}", err)))
+                .body(Body::from(format!(
+                    "request failed, see the logs of delivery {delivery_id}"
+                )))
                 .unwrap())
         }
     }
 }
 
+/// Checks that the request is authorized with `Authorization: Bearer <token>`,
+/// where the token is the value of `TRIAGEBOT_REPLAY_TOKEN`, returning the
+/// response to send if it isn't.
+///
+/// The endpoints that use it don't exist if the token isn't set.
+fn check_replay_token(req: &hyper::http::request::Parts) -> Option<Response<Body>> {
+    let expected_token = match env::var("TRIAGEBOT_REPLAY_TOKEN") {
+        Ok(token) => token,
+        Err(_) => {
+            return Some(
+                Response::builder()
+                    .status(StatusCode::NOT_FOUND)
+                    .body(Body::empty())
+                    .unwrap(),
+            );
+        }
+    };
+    let token = req
+        .headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    if token.len() != expected_token.len()
+        || !openssl::memcmp::eq(token.as_bytes(), expected_token.as_bytes())
+    {
+        return Some(
+            Response::builder()
+                .status(StatusCode::UNAUTHORIZED)
+                .body(Body::from("Invalid authorization"))
+                .unwrap(),
+        );
+    }
+    None
+}
+
+/// Replays a stored GitHub webhook delivery, given by the `delivery` query
+/// parameter.
+///
+/// Requests must be authorized, see [`check_replay_token`].
+async fn replay_event(req: &hyper::http::request::Parts, ctx: &Context) -> Response<Body> {
+    if let Some(response) = check_replay_token(req) {
+        return response;
+    }
+    if req.method != hyper::Method::POST {
+        return Response::builder()
+            .status(StatusCode::METHOD_NOT_ALLOWED)
+            .header(header::ALLOW, "POST")
+            .body(Body::empty())
+            .unwrap();
+    }
+    let delivery_id = req.uri.query().and_then(|query| {
+        url::form_urlencoded::parse(query.as_bytes())
+            .find(|(k, _)| k == "delivery")
+            .map(|(_, v)| v.into_owned())
+    });
+    let delivery_id = match delivery_id {
+        Some(id) => id,
+        None => {
+            return Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(Body::from(
+                    "Please provide `?delivery=<delivery id>` query param on URL.",
+                ))
+                .unwrap();
+        }
+    };
+
+    match triagebot::replay_webhook(&delivery_id, ctx).await {
+        Ok(true) => Response::new(Body::from("processed request")),
+        Ok(false) => Response::new(Body::from("ignored request")),
+        Err(err) => {
+            log::error!("replay of {} failed: {:?}", delivery_id, err);
+            Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Body::from(format!("replay failed: {:?}", err)))
+                .unwrap()
+        }
+    }
+}
+
+/// The largest `triagebot.toml` accepted by `/validate-config`.
+const MAX_CONFIG_SIZE: usize = 1024 * 1024;
+
+async fn validate_config(
+    req: &hyper::http::request::Parts,
+    contents: &[u8],
+    ctx: &Context,
+) -> Response<Body> {
+    let repo = req.uri.query().and_then(|query| {
+        url::form_urlencoded::parse(query.as_bytes())
+            .find(|(k, _)| k == "repo")
+            .map(|(_, v)| v.into_owned())
+    });
+    let repo = match repo.as_deref().map(|repo| repo.split_once('/')) {
+        None => None,
+        Some(Some((organization, repository))) => Some(github::IssueRepository {
+            organization: organization.to_string(),
+            repository: repository.to_string(),
+        }),
+        Some(None) => {
+            return Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(Body::from("`repo` must be of the form `<owner>/<repo>`"))
+                .unwrap();
+        }
+    };
+    // Checking labels spends the bot's API quota on any repository.
+    if repo.is_some() {
+        if let Some(response) = check_replay_token(req) {
+            return response;
+        }
+    }
+
+    match triagebot::handlers::validate_config::validate(&ctx.github, repo.as_ref(), contents).await
+    {
+        Ok(problems) if problems.is_empty() => Response::new(Body::from("No problems found.\n")),
+        Ok(problems) => {
+            let mut body = String::new();
+            for problem in problems {
+                body.push_str(&format!("- {}\n", problem.replace('\n', "\n  ")));
+            }
+            Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(Body::from(body))
+                .unwrap()
+        }
+        Err(err) => {
+            log::error!("config validation failed: {:?}", err);
+            Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Body::from(format!("validation failed: {:?}", err)))
+                .unwrap()
+        }
+    }
+}
+
 async fn run_server(addr: SocketAddr) -> anyhow::Result<()> {
     let pool = db::ClientPool::new();
     db::run_migrations(&*pool.get().await)
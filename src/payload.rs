@@ -1,39 +1,133 @@
 use openssl::{hash::MessageDigest, memcmp, pkey::PKey, sign::Signer};
 use std::fmt;
 
-#[derive(Debug)]
-pub struct SignedPayloadError;
+/// The environment variable holding the webhook secret.
+///
+/// Several secrets can be given, separated by commas, so that the secret can
+/// be rotated without downtime: add the new secret to the list, change it in
+/// the webhook settings on GitHub, and then remove the old one.
+const SECRET_VAR: &str = "GITHUB_WEBHOOK_SECRET";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SignedPayloadError {
+    /// `GITHUB_WEBHOOK_SECRET` is not set, or doesn't contain any secrets.
+    MissingSecret,
+    /// The signature doesn't start with `sha256=` or `sha1=`.
+    UnsupportedAlgorithm,
+    /// The signature isn't valid hex.
+    InvalidHex,
+    /// The signature doesn't match the payload for any of the secrets.
+    Mismatch,
+}
 
 impl fmt::Display for SignedPayloadError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "failed to validate payload")
+        match self {
+            SignedPayloadError::MissingSecret => write!(f, "{SECRET_VAR} is not configured"),
+            SignedPayloadError::UnsupportedAlgorithm => {
+                write!(f, "signature must be prefixed with `sha256=` or `sha1=`")
+            }
+            SignedPayloadError::InvalidHex => write!(f, "signature is not valid hex"),
+            SignedPayloadError::Mismatch => write!(f, "signature does not match payload"),
+        }
     }
 }
 
 impl std::error::Error for SignedPayloadError {}
 
+/// Checks the signature of a webhook payload against the configured secrets.
+///
+/// `signature` is the value of the `X-Hub-Signature-256` header (which should
+/// be preferred), or of the legacy sha1 `X-Hub-Signature` header.
 pub fn assert_signed(signature: &str, payload: &[u8]) -> Result<(), SignedPayloadError> {
-    let signature = signature.get("sha1=".len()..).ok_or(SignedPayloadError)?;
-    let signature = match hex::decode(&signature) {
+    let secrets = std::env::var(SECRET_VAR).map_err(|_| SignedPayloadError::MissingSecret)?;
+    let secrets: Vec<&str> = secrets
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .collect();
+    verify(signature, payload, &secrets)
+}
+
+fn verify(signature: &str, payload: &[u8], secrets: &[&str]) -> Result<(), SignedPayloadError> {
+    if secrets.is_empty() {
+        return Err(SignedPayloadError::MissingSecret);
+    }
+    let (digest, signature) = if let Some(signature) = signature.strip_prefix("sha256=") {
+        (MessageDigest::sha256(), signature)
+    } else if let Some(signature) = signature.strip_prefix("sha1=") {
+        (MessageDigest::sha1(), signature)
+    } else {
+        return Err(SignedPayloadError::UnsupportedAlgorithm);
+    };
+    let signature = match hex::decode(signature) {
         Ok(e) => e,
         Err(e) => {
             tracing::trace!("hex decode failed for {:?}: {:?}", signature, e);
-            return Err(SignedPayloadError);
+            return Err(SignedPayloadError::InvalidHex);
         }
     };
 
-    let key = PKey::hmac(
-        std::env::var("GITHUB_WEBHOOK_SECRET")
-            .expect("Missing GITHUB_WEBHOOK_SECRET")
-            .as_bytes(),
-    )
-    .unwrap();
-    let mut signer = Signer::new(MessageDigest::sha1(), &key).unwrap();
-    signer.update(&payload).unwrap();
-    let hmac = signer.sign_to_vec().unwrap();
-
-    if !memcmp::eq(&hmac, &signature) {
-        return Err(SignedPayloadError);
+    for secret in secrets {
+        let key = PKey::hmac(secret.as_bytes()).unwrap();
+        let mut signer = Signer::new(digest, &key).unwrap();
+        signer.update(payload).unwrap();
+        let hmac = signer.sign_to_vec().unwrap();
+
+        if hmac.len() == signature.len() && memcmp::eq(&hmac, &signature) {
+            return Ok(());
+        }
+    }
+    Err(SignedPayloadError::Mismatch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The example from GitHub's documentation on validating webhook deliveries.
+    const SECRET: &str = "It's a Secret to Everybody";
+    const PAYLOAD: &[u8] = b"Hello, World!";
+    const SHA256: &str = "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17";
+    const SHA1: &str = "sha1=01dc10d0c83e72ed246219cdd91669667fe2ca59";
+
+    #[test]
+    fn valid_signatures() {
+        assert_eq!(verify(SHA256, PAYLOAD, &[SECRET]), Ok(()));
+        assert_eq!(verify(SHA1, PAYLOAD, &[SECRET]), Ok(()));
+    }
+
+    #[test]
+    fn rotated_secrets() {
+        assert_eq!(verify(SHA256, PAYLOAD, &["new secret", SECRET]), Ok(()));
+        assert_eq!(verify(SHA256, PAYLOAD, &[SECRET, "new secret"]), Ok(()));
+    }
+
+    #[test]
+    fn invalid_signatures() {
+        assert_eq!(
+            verify(SHA256, b"Goodbye, World!", &[SECRET]),
+            Err(SignedPayloadError::Mismatch)
+        );
+        assert_eq!(
+            verify(SHA256, PAYLOAD, &["wrong"]),
+            Err(SignedPayloadError::Mismatch)
+        );
+        assert_eq!(
+            verify("sha256=01dc", PAYLOAD, &[SECRET]),
+            Err(SignedPayloadError::Mismatch)
+        );
+        assert_eq!(
+            verify("sha256=xyz", PAYLOAD, &[SECRET]),
+            Err(SignedPayloadError::InvalidHex)
+        );
+        assert_eq!(
+            verify("md5=01dc", PAYLOAD, &[SECRET]),
+            Err(SignedPayloadError::UnsupportedAlgorithm)
+        );
+        assert_eq!(
+            verify(SHA256, PAYLOAD, &[]),
+            Err(SignedPayloadError::MissingSecret)
+        );
     }
-    Ok(())
 }
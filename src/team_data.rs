@@ -1,10 +1,10 @@
 use crate::github::GithubClient;
 use anyhow::Context as _;
-use rust_team_data::v1::{Teams, ZulipMapping, BASE_URL};
+use rust_team_data::v1::{Teams, ZulipMapping};
 use serde::de::DeserializeOwned;
 
 async fn by_url<T: DeserializeOwned>(client: &GithubClient, path: &str) -> anyhow::Result<T> {
-    let url = format!("{}{}", BASE_URL, path);
+    let url = format!("{}{}", client.team_api_url(), path);
     for _ in 0i32..3 {
         let map: Result<T, _> = client.json(client.raw().get(&url)).await;
         match map {
@@ -0,0 +1,136 @@
+//! Recording of the interactions triagebot has with GitHub, for building
+//! test fixtures.
+//!
+//! When the `TRIAGEBOT_TEST_RECORD` environment variable is set to a
+//! directory, every webhook delivery the server receives and every request
+//! sent through [`GithubClient`](crate::github::GithubClient) (along with its
+//! response) is written to that directory as a numbered JSON file containing
+//! an [`Activity`].
+//!
+//! The fake GitHub server used by the tests in the `tests` directory replays
+//! those files, so a recording of a real interaction with a test repository
+//! can be copied into a fixture directory and turned into a test.
+
+use anyhow::Context as _;
+use once_cell::sync::Lazy;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tracing as log;
+
+static RECORD_DIR: Lazy<Option<PathBuf>> = Lazy::new(|| {
+    let dir = PathBuf::from(std::env::var_os("TRIAGEBOT_TEST_RECORD")?);
+    if let Err(e) = std::fs::create_dir_all(&dir) {
+        log::error!("failed to create record directory {}: {e}", dir.display());
+        return None;
+    }
+    log::info!("recording activity to {}", dir.display());
+    Some(dir)
+});
+
+static NEXT_SEQUENCE: AtomicUsize = AtomicUsize::new(0);
+
+/// The service a recorded request was sent to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Service {
+    /// The GitHub REST API.
+    Api,
+    /// The GitHub GraphQL API.
+    Graphql,
+    /// `raw.githubusercontent.com`.
+    Raw,
+    /// The Rust team API.
+    TeamApi,
+}
+
+/// A single recorded interaction.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind")]
+pub enum Activity {
+    /// A webhook delivery sent by GitHub.
+    Webhook {
+        /// The value of the `X-GitHub-Event` header.
+        webhook_event: String,
+        payload: serde_json::Value,
+    },
+    /// A request sent by triagebot, and the response to it.
+    ///
+    /// Bodies that are not JSON (such as diffs or raw files) are stored as a
+    /// JSON string. Missing bodies are `null`.
+    Request {
+        service: Service,
+        method: String,
+        /// The path and query of the request, relative to the base URL of
+        /// the service.
+        path: String,
+        request_body: serde_json::Value,
+        response_code: u16,
+        response_body: serde_json::Value,
+    },
+}
+
+/// Returns whether or not activity is being recorded.
+pub fn is_recording() -> bool {
+    RECORD_DIR.is_some()
+}
+
+/// Records a webhook delivery, if recording is enabled.
+pub fn record_event(event: &str, payload: &str) {
+    if !is_recording() {
+        return;
+    }
+    record(&Activity::Webhook {
+        webhook_event: event.to_string(),
+        payload: body_to_value(payload.as_bytes()),
+    });
+}
+
+/// Records a request and its response, if recording is enabled.
+pub(crate) fn record_request(
+    service: Service,
+    method: &str,
+    path: &str,
+    request_body: Option<&[u8]>,
+    response_code: u16,
+    response_body: &[u8],
+) {
+    if !is_recording() {
+        return;
+    }
+    record(&Activity::Request {
+        service,
+        method: method.to_string(),
+        path: path.to_string(),
+        request_body: request_body.map_or(serde_json::Value::Null, body_to_value),
+        response_code,
+        response_body: body_to_value(response_body),
+    });
+}
+
+/// Converts a body to JSON, falling back to a string for non-JSON bodies.
+pub fn body_to_value(body: &[u8]) -> serde_json::Value {
+    if body.is_empty() {
+        return serde_json::Value::Null;
+    }
+    serde_json::from_slice(body)
+        .unwrap_or_else(|_| serde_json::Value::String(String::from_utf8_lossy(body).into_owned()))
+}
+
+fn record(activity: &Activity) {
+    let dir = match &*RECORD_DIR {
+        Some(dir) => dir,
+        None => return,
+    };
+    let kind = match activity {
+        Activity::Webhook { .. } => "webhook",
+        Activity::Request { .. } => "request",
+    };
+    let sequence = NEXT_SEQUENCE.fetch_add(1, Ordering::SeqCst);
+    let path = dir.join(format!("{sequence:03}-{kind}.json"));
+    let res = serde_json::to_string_pretty(activity)
+        .context("failed to serialize activity")
+        .and_then(|json| std::fs::write(&path, json).context("failed to write activity"));
+    if let Err(e) = res {
+        log::error!("failed to record {}: {e:?}", path.display());
+    }
+}
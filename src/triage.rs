@@ -1,9 +1,12 @@
+use crate::db::review_prefs;
 use crate::handlers::Context;
 use chrono::{Duration, Utc};
 use hyper::{Body, Response, StatusCode};
 use serde::Serialize;
 use serde_json::value::{to_value, Value};
+use std::collections::HashMap;
 use std::sync::Arc;
+use tracing as log;
 use url::Url;
 
 const YELLOW_DAYS: i64 = 7;
@@ -17,11 +20,13 @@ pub fn index() -> Result<Response<Body>, hyper::Error> {
         .unwrap())
 }
 
-pub async fn pulls(
-    ctx: Arc<Context>,
+/// Lists the open pull requests of a repository, least recently updated
+/// first. Returns `None` if the repository can't be found.
+async fn list_pulls(
+    ctx: &Context,
     owner: &str,
     repo: &str,
-) -> Result<Response<Body>, hyper::Error> {
+) -> Option<Vec<octocrab::models::pulls::PullRequest>> {
     let octocrab = &ctx.octocrab;
     let res = octocrab
         .pulls(owner, repo)
@@ -31,15 +36,7 @@ pub async fn pulls(
         .per_page(100)
         .send()
         .await;
-    let mut page = match res {
-        Ok(page) => page,
-        Err(_) => {
-            return Ok(Response::builder()
-                .status(StatusCode::NOT_FOUND)
-                .body(Body::from("The repository is not found."))
-                .unwrap());
-        }
-    };
+    let mut page = res.ok()?;
     let mut base_pulls = page.take_items();
     let mut next_page = page.next;
     while let Some(mut page) = octocrab
@@ -50,6 +47,24 @@ pub async fn pulls(
         base_pulls.extend(page.take_items());
         next_page = page.next;
     }
+    Some(base_pulls)
+}
+
+fn repo_not_found() -> Result<Response<Body>, hyper::Error> {
+    Ok(Response::builder()
+        .status(StatusCode::NOT_FOUND)
+        .body(Body::from("The repository is not found."))
+        .unwrap())
+}
+
+pub async fn pulls(
+    ctx: Arc<Context>,
+    owner: &str,
+    repo: &str,
+) -> Result<Response<Body>, hyper::Error> {
+    let Some(base_pulls) = list_pulls(&ctx, owner, repo).await else {
+        return repo_not_found();
+    };
 
     let mut pulls: Vec<Value> = Vec::new();
     for base_pull in base_pulls.into_iter() {
@@ -130,3 +145,213 @@ struct PullRequest {
     pub wait_for_review: bool,
     pub days_from_last_updated_at: i64,
 }
+
+/// Shows the open pull requests assigned to each reviewer of a repository.
+///
+/// With `json` set, the data is returned as JSON instead of HTML.
+pub async fn reviewers(
+    ctx: Arc<Context>,
+    owner: &str,
+    repo: &str,
+    json: bool,
+) -> Result<Response<Body>, hyper::Error> {
+    let Some(base_pulls) = list_pulls(&ctx, owner, repo).await else {
+        return repo_not_found();
+    };
+    let now = Utc::now();
+    let pulls = base_pulls
+        .into_iter()
+        .filter(|pull| !pull.draft.unwrap_or(false))
+        .map(|pull| {
+            let labels: Vec<String> = pull
+                .labels
+                .unwrap_or_default()
+                .into_iter()
+                .map(|label| label.name)
+                .collect();
+            let updated_at = pull.updated_at.unwrap_or(pull.created_at);
+            let assignees = pull
+                .assignees
+                .unwrap_or_default()
+                .into_iter()
+                .map(|user| user.login)
+                .collect();
+            ReviewerPullRequest {
+                html_url: pull.html_url,
+                number: pull.number,
+                title: pull.title,
+                author: pull.user.login,
+                assignees,
+                status: labels
+                    .into_iter()
+                    .find(|label| label.starts_with("S-waiting-on-")),
+                days_since_activity: (now - updated_at).num_days(),
+            }
+        })
+        .collect();
+    let mut reviewers = group_by_reviewer(pulls);
+
+    // The availability is only informative, so the page still works without
+    // the database.
+    let logins: Vec<&str> = reviewers.iter().map(|r| r.login.as_str()).collect();
+    let prefs = match ctx.db.try_get().await {
+        Ok(db) => review_prefs::prefs_by_username(&db, &logins).await,
+        Err(e) => Err(e),
+    };
+    match prefs {
+        Ok(mut prefs) => {
+            let today = now.date().naive_utc();
+            for reviewer in &mut reviewers {
+                if let Some(prefs) = prefs.remove(&reviewer.login.to_lowercase()) {
+                    if prefs.is_away(today) {
+                        reviewer.away_until = prefs.away_until.map(|d| d.to_string());
+                    }
+                    reviewer.max_assigned_prs = prefs.max_assigned_prs;
+                }
+            }
+        }
+        Err(e) => log::warn!("failed to get review preferences: {e:?}"),
+    }
+
+    if json {
+        return Ok(Response::builder()
+            .header("Content-Type", "application/json")
+            .status(StatusCode::OK)
+            .body(Body::from(serde_json::to_string(&reviewers).unwrap()))
+            .unwrap());
+    }
+
+    let mut context = tera::Context::new();
+    context.insert("reviewers", &reviewers);
+    context.insert("owner", &owner);
+    context.insert("repo", &repo);
+    context.insert("yellow_days", &YELLOW_DAYS);
+    context.insert("red_days", &RED_DAYS);
+
+    let tera = tera::Tera::new("templates/triage/**/*").unwrap();
+    let body = Body::from(tera.render("reviewers.html", &context).unwrap());
+
+    Ok(Response::builder()
+        .header("Content-Type", "text/html")
+        .status(StatusCode::OK)
+        .body(body)
+        .unwrap())
+}
+
+/// Groups pull requests by assignee, with the reviewers that have the most
+/// PRs waiting on them first.
+fn group_by_reviewer(pulls: Vec<ReviewerPullRequest>) -> Vec<Reviewer> {
+    let mut by_login: HashMap<String, Reviewer> = HashMap::new();
+    for pull in pulls {
+        for assignee in &pull.assignees {
+            let reviewer = by_login
+                .entry(assignee.to_lowercase())
+                .or_insert_with(|| Reviewer {
+                    login: assignee.clone(),
+                    ..Reviewer::default()
+                });
+            match pull.status.as_deref() {
+                Some("S-waiting-on-review") => reviewer.waiting_on_review += 1,
+                Some("S-waiting-on-author") => reviewer.waiting_on_author += 1,
+                _ => {}
+            }
+            reviewer.max_days_since_activity = reviewer
+                .max_days_since_activity
+                .max(pull.days_since_activity);
+            reviewer.pulls.push(pull.clone());
+        }
+    }
+    let mut reviewers: Vec<Reviewer> = by_login.into_values().collect();
+    for reviewer in &mut reviewers {
+        reviewer
+            .pulls
+            .sort_by_key(|pull| std::cmp::Reverse(pull.days_since_activity));
+    }
+    reviewers.sort_by(|a, b| {
+        b.waiting_on_review
+            .cmp(&a.waiting_on_review)
+            .then(b.pulls.len().cmp(&a.pulls.len()))
+            .then(a.login.cmp(&b.login))
+    });
+    reviewers
+}
+
+#[derive(Serialize, Default)]
+struct Reviewer {
+    pub login: String,
+    pub waiting_on_review: usize,
+    pub waiting_on_author: usize,
+    /// The number of days since the least recently updated assigned PR was
+    /// updated.
+    pub max_days_since_activity: i64,
+    /// Set if the reviewer is currently away, as set through Zulip.
+    pub away_until: Option<String>,
+    /// The review capacity set through Zulip.
+    pub max_assigned_prs: Option<i32>,
+    pub pulls: Vec<ReviewerPullRequest>,
+}
+
+#[derive(Serialize, Clone)]
+struct ReviewerPullRequest {
+    pub html_url: Url,
+    pub number: u64,
+    pub title: String,
+    pub author: String,
+    pub assignees: Vec<String>,
+    /// The `S-waiting-on-*` label of the PR, if any.
+    pub status: Option<String>,
+    pub days_since_activity: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pull(
+        number: u64,
+        assignees: &[&str],
+        status: Option<&str>,
+        days: i64,
+    ) -> ReviewerPullRequest {
+        ReviewerPullRequest {
+            html_url: Url::parse(&format!("https://github.com/rust-lang/rust/pull/{number}"))
+                .unwrap(),
+            number,
+            title: format!("PR {number}"),
+            author: "contributor".to_string(),
+            assignees: assignees.iter().map(|a| a.to_string()).collect(),
+            status: status.map(|s| s.to_string()),
+            days_since_activity: days,
+        }
+    }
+
+    #[test]
+    fn grouping() {
+        let reviewers = group_by_reviewer(vec![
+            pull(1, &["alice"], Some("S-waiting-on-author"), 3),
+            pull(2, &["bob"], Some("S-waiting-on-review"), 20),
+            pull(3, &["alice", "Bob"], Some("S-waiting-on-review"), 1),
+            pull(4, &[], Some("S-waiting-on-review"), 40),
+            pull(5, &["alice"], None, 8),
+        ]);
+        let summary: Vec<_> = reviewers
+            .iter()
+            .map(|r| {
+                (
+                    r.login.as_str(),
+                    r.waiting_on_review,
+                    r.waiting_on_author,
+                    r.max_days_since_activity,
+                    r.pulls.iter().map(|p| p.number).collect::<Vec<_>>(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("bob", 2, 0, 20, vec![2, 3]),
+                ("alice", 1, 1, 8, vec![5, 1, 3]),
+            ]
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use crate::db::notifications::add_metadata;
 use crate::db::notifications::{self, delete_ping, move_indices, record_ping, Identifier};
+use crate::db::review_prefs;
 use crate::github::{self, GithubClient};
 use crate::handlers::docs_update::docs_update;
 use crate::handlers::Context;
@@ -157,6 +158,14 @@ fn handle_command<'a>(
                 .map_err(|e| format_err!("Failed to parse movement, expected `move <from> <to>`: {e:?}.")),
             Some("meta") => add_meta_notification(&ctx, gh_id, words).await
                 .map_err(|e| format_err!("Failed to parse movement, expected `move <idx> <meta...>`: {e:?}.")),
+            Some("away") => set_away(ctx, gh_id, words).await
+                .map_err(|e| format_err!("Failed to parse away date, expected `away until <YYYY-MM-DD>`: {e:?}.")),
+            Some("back") => set_back(ctx, gh_id, words).await
+                .map_err(|e| format_err!("Failed to parse, expected `back`: {e:?}.")),
+            Some("capacity") => set_capacity(ctx, gh_id, words).await
+                .map_err(|e| format_err!("Failed to parse capacity, expected `capacity <number of PRs|none>`: {e:?}.")),
+            Some("availability") => show_availability(ctx, gh_id).await
+                .map_err(|e| format_err!("Failed to get availability: {e:?}.")),
             _ => {
                 while let Some(word) = next {
                     if word == "@**triagebot**" {
@@ -638,6 +647,92 @@ async fn move_notification(
     }
 }
 
+async fn set_away(
+    ctx: &Context,
+    gh_id: i64,
+    mut words: impl Iterator<Item = &str>,
+) -> anyhow::Result<Option<String>> {
+    if words.next() != Some("until") {
+        anyhow::bail!("`until` not present");
+    }
+    let until = match words.next() {
+        Some(until) => until,
+        None => anyhow::bail!("date not present"),
+    };
+    if words.next().is_some() {
+        anyhow::bail!("too many words");
+    }
+    let until = chrono::NaiveDate::parse_from_str(until, "%Y-%m-%d").context("date")?;
+    if until <= chrono::Utc::today().naive_utc() {
+        anyhow::bail!("the date must be in the future");
+    }
+    let user = github::User::by_id(&ctx.github, gh_id).await?;
+    let db = ctx.db.get().await;
+    review_prefs::set_away_until(&db, gh_id, &user.login, Some(until)).await?;
+    Ok(Some(format!(
+        "You won't be assigned PRs to review until {until}. Use `back` to end this early."
+    )))
+}
+
+async fn set_back(
+    ctx: &Context,
+    gh_id: i64,
+    mut words: impl Iterator<Item = &str>,
+) -> anyhow::Result<Option<String>> {
+    if words.next().is_some() {
+        anyhow::bail!("too many words");
+    }
+    let user = github::User::by_id(&ctx.github, gh_id).await?;
+    let db = ctx.db.get().await;
+    review_prefs::set_away_until(&db, gh_id, &user.login, None).await?;
+    Ok(Some(
+        "Welcome back! You can be assigned PRs again.".to_string(),
+    ))
+}
+
+async fn set_capacity(
+    ctx: &Context,
+    gh_id: i64,
+    mut words: impl Iterator<Item = &str>,
+) -> anyhow::Result<Option<String>> {
+    let capacity = match words.next() {
+        Some("none") => None,
+        Some(capacity) => Some(capacity.parse::<i32>().context("number of PRs")?),
+        None => anyhow::bail!("number of PRs not present"),
+    };
+    if words.next().is_some() {
+        anyhow::bail!("too many words");
+    }
+    if matches!(capacity, Some(c) if c < 0) {
+        anyhow::bail!("the number of PRs can't be negative");
+    }
+    let user = github::User::by_id(&ctx.github, gh_id).await?;
+    let db = ctx.db.get().await;
+    review_prefs::set_max_assigned_prs(&db, gh_id, &user.login, capacity).await?;
+    Ok(Some(match capacity {
+        Some(capacity) => {
+            format!("You won't be assigned more than {capacity} open PRs to review.")
+        }
+        None => "Your review capacity is no longer limited.".to_string(),
+    }))
+}
+
+async fn show_availability(ctx: &Context, gh_id: i64) -> anyhow::Result<Option<String>> {
+    let db = ctx.db.get().await;
+    let prefs = review_prefs::get_prefs(&db, gh_id).await?;
+    let mut resp = String::new();
+    if prefs.is_away(chrono::Utc::today().naive_utc()) {
+        writeln!(resp, "You are away until {}.", prefs.away_until.unwrap()).unwrap();
+    } else {
+        writeln!(resp, "You are available for reviews.").unwrap();
+    }
+    match prefs.max_assigned_prs {
+        Some(max) => writeln!(resp, "You can be assigned up to {max} open PRs.").unwrap(),
+        None => writeln!(resp, "Your review capacity is not limited.").unwrap(),
+    }
+    Ok(Some(resp))
+}
+
 #[derive(serde::Serialize, Debug)]
 struct ResponseNotRequired {
     response_not_required: bool,
@@ -0,0 +1,313 @@
+//! A fake GitHub for end-to-end tests of the webhook handlers.
+//!
+//! [`FakeGithub`] runs a small HTTP server on a local port which stands in
+//! for the GitHub REST API, the GraphQL API, `raw.githubusercontent.com`, and
+//! the Rust team API. It answers requests with the canned responses from a
+//! fixture directory (see [`triagebot::test_record`] for how fixtures can be
+//! recorded), and keeps every request it receives so that tests can check
+//! which comments, labels, and assignees triagebot tried to set.
+
+#![allow(dead_code)]
+
+use reqwest::Client;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use triagebot::github::GithubClient;
+use triagebot::handlers::Context;
+use triagebot::test_record::{Activity, Service};
+use triagebot::EventName;
+
+/// A request received by [`FakeGithub`].
+#[derive(Debug, Clone)]
+pub struct Received {
+    pub service: Service,
+    pub method: String,
+    pub path: String,
+    pub body: serde_json::Value,
+}
+
+struct Canned {
+    activity: Activity,
+    used: bool,
+}
+
+pub struct FakeGithub {
+    addr: String,
+    canned: Arc<Mutex<Vec<Canned>>>,
+    received: Arc<Mutex<Vec<Received>>>,
+    webhooks: Vec<(String, serde_json::Value)>,
+}
+
+impl FakeGithub {
+    /// Starts a server with the activities recorded in the given directory
+    /// of `tests/fixtures`.
+    ///
+    /// Files are processed in lexicographic order. Webhooks are kept to be
+    /// delivered by [`FakeGithub::deliver_webhooks`]; requests are served as
+    /// canned responses.
+    pub fn start(fixture: &str) -> FakeGithub {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(fixture);
+        let mut paths: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap_or_else(|e| panic!("failed to read {}: {e}", dir.display()))
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension() == Some("json".as_ref()))
+            .collect();
+        paths.sort();
+        let mut canned = Vec::new();
+        let mut webhooks = Vec::new();
+        for path in paths {
+            let contents = std::fs::read_to_string(&path).unwrap();
+            let activity: Activity = serde_json::from_str(&contents)
+                .unwrap_or_else(|e| panic!("failed to parse {}: {e}", path.display()));
+            match activity {
+                Activity::Webhook {
+                    webhook_event,
+                    payload,
+                } => webhooks.push((webhook_event, payload)),
+                activity @ Activity::Request { .. } => canned.push(Canned {
+                    activity,
+                    used: false,
+                }),
+            }
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = format!("http://{}", listener.local_addr().unwrap());
+        let canned = Arc::new(Mutex::new(canned));
+        let received = Arc::new(Mutex::new(Vec::new()));
+        {
+            let canned = canned.clone();
+            let received = received.clone();
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let stream = match stream {
+                        Ok(stream) => stream,
+                        Err(_) => return,
+                    };
+                    let canned = canned.clone();
+                    let received = received.clone();
+                    std::thread::spawn(move || serve_connection(stream, &canned, &received));
+                }
+            });
+        }
+        FakeGithub {
+            addr,
+            canned,
+            received,
+            webhooks,
+        }
+    }
+
+    /// A client that sends all of its requests to this server.
+    pub fn client(&self) -> GithubClient {
+        GithubClient::new(Client::new(), "fake-token".to_string()).with_urls(
+            &self.addr,
+            &format!("{}/graphql", self.addr),
+            &format!("{}/raw", self.addr),
+            &format!("{}/team-api", self.addr),
+        )
+    }
+
+    /// A handler context using [`FakeGithub::client`].
+    ///
+    /// The database pool is only connected on first use, so handlers that
+    /// need the database will fail unless `DATABASE_URL` is set.
+    pub fn context(&self) -> Context {
+        Context {
+            github: self.client(),
+            db: triagebot::db::ClientPool::new(),
+            username: "rustbot".to_string(),
+            octocrab: octocrab::OctocrabBuilder::new()
+                .personal_token("fake-token".to_string())
+                .build()
+                .unwrap(),
+        }
+    }
+
+    /// Runs every webhook from the fixture through [`triagebot::webhook`],
+    /// in order, panicking if any of them fail.
+    pub async fn deliver_webhooks(&self, ctx: &Context) {
+        for (event, payload) in &self.webhooks {
+            let event: EventName = event.parse().unwrap();
+            triagebot::webhook(event, payload.to_string(), ctx)
+                .await
+                .unwrap_or_else(|e| panic!("webhook failed: {e:?}"));
+        }
+    }
+
+    /// All requests received so far.
+    pub fn received(&self) -> Vec<Received> {
+        self.received.lock().unwrap().clone()
+    }
+
+    /// Requests to the REST API which modify something.
+    pub fn mutations(&self) -> Vec<Received> {
+        self.received()
+            .into_iter()
+            .filter(|r| r.service == Service::Api && r.method != "GET")
+            .collect()
+    }
+
+    /// The bodies of all comments posted.
+    pub fn comments(&self) -> Vec<String> {
+        self.mutations()
+            .into_iter()
+            .filter(|r| r.method == "POST" && r.path.ends_with("/comments"))
+            .map(|r| r.body["body"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    /// All labels added.
+    pub fn added_labels(&self) -> Vec<String> {
+        self.mutations()
+            .into_iter()
+            .filter(|r| r.method == "POST" && r.path.ends_with("/labels"))
+            .flat_map(|r| string_array(&r.body["labels"]))
+            .collect()
+    }
+
+    /// All labels removed.
+    pub fn removed_labels(&self) -> Vec<String> {
+        self.mutations()
+            .into_iter()
+            .filter(|r| r.method == "DELETE" && r.path.contains("/labels/"))
+            .map(|r| r.path.rsplit('/').next().unwrap().to_string())
+            .collect()
+    }
+
+    /// All users added as assignees.
+    pub fn added_assignees(&self) -> Vec<String> {
+        self.mutations()
+            .into_iter()
+            .filter(|r| r.method == "POST" && r.path.ends_with("/assignees"))
+            .flat_map(|r| string_array(&r.body["assignees"]))
+            .collect()
+    }
+
+    /// All users removed as assignees.
+    pub fn removed_assignees(&self) -> Vec<String> {
+        self.mutations()
+            .into_iter()
+            .filter(|r| r.method == "DELETE" && r.path.ends_with("/assignees"))
+            .flat_map(|r| string_array(&r.body["assignees"]))
+            .collect()
+    }
+}
+
+fn string_array(value: &serde_json::Value) -> Vec<String> {
+    value
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|v| v.as_str().unwrap().to_string())
+        .collect()
+}
+
+/// Splits a request path into the service it was sent to and the path
+/// relative to that service's base URL.
+fn split_service(path: &str) -> (Service, &str) {
+    for (service, prefix) in [
+        (Service::Graphql, "/graphql"),
+        (Service::Raw, "/raw"),
+        (Service::TeamApi, "/team-api"),
+    ] {
+        if let Some(rest) = path.strip_prefix(prefix) {
+            if rest.is_empty() || rest.starts_with('/') {
+                return (service, rest);
+            }
+        }
+    }
+    (Service::Api, path)
+}
+
+/// Finds the response for a request.
+///
+/// Canned responses for the same request are used in the order they were
+/// recorded. Once they are all used, the last one is reused.
+fn find_response(canned: &mut [Canned], received: &Received) -> Option<(u16, serde_json::Value)> {
+    let matches = |c: &Canned| match &c.activity {
+        Activity::Request {
+            service,
+            method,
+            path,
+            ..
+        } => *service == received.service && *method == received.method && *path == received.path,
+        Activity::Webhook { .. } => false,
+    };
+    let index = canned
+        .iter()
+        .position(|c| !c.used && matches(c))
+        .or_else(|| canned.iter().rposition(matches))?;
+    canned[index].used = true;
+    match &canned[index].activity {
+        Activity::Request {
+            response_code,
+            response_body,
+            ..
+        } => Some((*response_code, response_body.clone())),
+        Activity::Webhook { .. } => unreachable!(),
+    }
+}
+
+fn serve_connection(
+    stream: TcpStream,
+    canned: &Mutex<Vec<Canned>>,
+    received: &Mutex<Vec<Received>>,
+) {
+    let mut writer = stream.try_clone().unwrap();
+    let mut reader = BufReader::new(stream);
+    loop {
+        // Request line, e.g. `GET /repos/rust-lang/rust HTTP/1.1`.
+        let mut line = String::new();
+        if reader.read_line(&mut line).unwrap_or(0) == 0 {
+            return;
+        }
+        let mut parts = line.split_whitespace();
+        let method = parts.next().unwrap().to_string();
+        let path = parts.next().unwrap().to_string();
+        let mut content_length = 0;
+        loop {
+            let mut header = String::new();
+            reader.read_line(&mut header).unwrap();
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().unwrap();
+                }
+            }
+        }
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).unwrap();
+
+        let (service, relative) = split_service(&path);
+        let request = Received {
+            service,
+            method,
+            path: relative.to_string(),
+            body: triagebot::test_record::body_to_value(&body),
+        };
+        let response = find_response(&mut canned.lock().unwrap(), &request);
+        received.lock().unwrap().push(request);
+        let (code, body) =
+            response.unwrap_or_else(|| (404, serde_json::json!({"message": "Not Found"})));
+        let body = match body {
+            serde_json::Value::Null => String::new(),
+            serde_json::Value::String(s) => s,
+            body => body.to_string(),
+        };
+        let response = format!(
+            "HTTP/1.1 {code} Fake\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}",
+            body.len()
+        );
+        if writer.write_all(response.as_bytes()).is_err() {
+            return;
+        }
+    }
+}
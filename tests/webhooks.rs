@@ -0,0 +1,234 @@
+//! End-to-end tests which feed recorded webhooks into `triagebot::webhook`,
+//! with a fake GitHub behind the client.
+//!
+//! Each test uses a directory in `tests/fixtures` containing the webhook
+//! payloads and the responses of the fake GitHub. Fixtures can be captured
+//! from a real interaction with `TRIAGEBOT_TEST_RECORD`, see
+//! `triagebot::test_record`.
+//!
+//! The configuration cache is global, so each fixture should use a distinct
+//! repository name.
+
+mod common;
+
+use common::FakeGithub;
+use triagebot::test_record::Service;
+
+#[tokio::test]
+async fn assign_new_pr() {
+    let github = FakeGithub::start("assign_new_pr");
+    let ctx = github.context();
+    github.deliver_webhooks(&ctx).await;
+
+    assert_eq!(github.added_assignees(), ["compiler-reviewer"]);
+    assert_eq!(
+        github.comments(),
+        [
+            "Thanks for the pull request, and welcome! The Rust team is excited to review \
+             your changes, and you should hear from @compiler-reviewer (or someone else) soon."
+        ]
+    );
+}
+
+#[tokio::test]
+async fn relabel_comment() {
+    let github = FakeGithub::start("relabel_comment");
+    let ctx = github.context();
+    github.deliver_webhooks(&ctx).await;
+
+    assert_eq!(github.added_labels(), ["A-diagnostics"]);
+    assert_eq!(github.removed_labels(), ["C-bug"]);
+    assert!(github.comments().is_empty());
+}
+
+#[tokio::test]
+async fn dry_run_relabel() {
+    let github = FakeGithub::start("dry_run_relabel");
+    let ctx = github.context();
+    github.deliver_webhooks(&ctx).await;
+
+    assert!(github.mutations().is_empty());
+    let actions: Vec<_> = triagebot::dry_run::actions(Some("rust-lang/dry-run-test"))
+        .into_iter()
+        .map(|action| (action.action, action.details))
+        .collect();
+    assert_eq!(
+        actions,
+        [
+            ("remove_label".to_string(), "C-bug".to_string()),
+            ("add_labels".to_string(), "A-diagnostics".to_string()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn push_refreshes_config() {
+    let github = FakeGithub::start("config_push");
+    let ctx = github.context();
+    github.deliver_webhooks(&ctx).await;
+
+    // The config is fetched at the pushed commit, not from the branch.
+    let raw_requests: Vec<_> = github
+        .received()
+        .into_iter()
+        .filter(|r| r.service == Service::Raw)
+        .map(|r| r.path)
+        .collect();
+    assert_eq!(
+        raw_requests,
+        [
+            "/rust-lang/config-push-test/2222222222222222222222222222222222222222/triagebot.toml",
+            "/rust-lang/.github/HEAD/triagebot.toml",
+        ]
+    );
+    let cached = triagebot::config::cached_configs()
+        .into_iter()
+        .find(|c| c.repo == "rust-lang/config-push-test")
+        .unwrap();
+    assert!(cached.config.unwrap().contains("\"T-*\""));
+}
+
+#[tokio::test]
+async fn shortcut_ready() {
+    let github = FakeGithub::start("shortcut_ready");
+    let ctx = github.context();
+    github.deliver_webhooks(&ctx).await;
+
+    assert_eq!(github.removed_labels(), ["S-waiting-on-author"]);
+    assert_eq!(github.added_labels(), ["S-waiting-on-review"]);
+    let requested: Vec<_> = github
+        .mutations()
+        .into_iter()
+        .filter(|r| r.path.ends_with("/requested_reviewers"))
+        .map(|r| r.body)
+        .collect();
+    assert_eq!(
+        requested,
+        [serde_json::json!({"reviewers": ["compiler-reviewer"]})]
+    );
+}
+
+#[tokio::test]
+async fn required_reviews_pages() {
+    let github = FakeGithub::start("required_reviews_pages");
+    let ctx = github.context();
+    github.deliver_webhooks(&ctx).await;
+
+    // The approval is on the second page of reviews.
+    let statuses: Vec<_> = github
+        .mutations()
+        .into_iter()
+        .filter(|r| r.path.contains("/statuses/"))
+        .map(|r| r.body)
+        .collect();
+    assert_eq!(
+        statuses,
+        [serde_json::json!({
+            "state": "success",
+            "context": "triagebot/required-reviews",
+            "description": "Approved by the required reviewers",
+        })]
+    );
+}
+
+#[tokio::test]
+async fn review_mode_both() {
+    let github = FakeGithub::start("review_mode_both");
+    let ctx = github.context();
+    github.deliver_webhooks(&ctx).await;
+
+    // `r?`, `claim`, and `release-assignment` keep the assignees and the
+    // requested reviewers in sync.
+    let mutations: Vec<_> = github
+        .mutations()
+        .into_iter()
+        .map(|r| {
+            let path = r
+                .path
+                .trim_start_matches("/repos/rust-lang/review-mode-test/");
+            (r.method.clone(), path.to_string(), r.body)
+        })
+        .collect();
+    let mutation = |method: &str, path: &str, body| (method.to_string(), path.to_string(), body);
+    assert_eq!(
+        mutations,
+        [
+            // r? compiler-reviewer
+            mutation(
+                "POST",
+                "issues/7/assignees",
+                serde_json::json!({"assignees": ["compiler-reviewer"]})
+            ),
+            mutation(
+                "DELETE",
+                "issues/7/assignees",
+                serde_json::json!({"assignees": ["old-reviewer"]})
+            ),
+            mutation(
+                "POST",
+                "pulls/7/requested_reviewers",
+                serde_json::json!({"reviewers": ["compiler-reviewer"]})
+            ),
+            mutation(
+                "DELETE",
+                "pulls/7/requested_reviewers",
+                serde_json::json!({"reviewers": ["old-reviewer"]})
+            ),
+            // @rustbot claim
+            mutation(
+                "POST",
+                "issues/7/assignees",
+                serde_json::json!({"assignees": ["triager"]})
+            ),
+            mutation(
+                "DELETE",
+                "issues/7/assignees",
+                serde_json::json!({"assignees": ["compiler-reviewer"]})
+            ),
+            mutation(
+                "POST",
+                "pulls/7/requested_reviewers",
+                serde_json::json!({"reviewers": ["triager"]})
+            ),
+            mutation(
+                "DELETE",
+                "pulls/7/requested_reviewers",
+                serde_json::json!({"reviewers": ["compiler-reviewer"]})
+            ),
+            // @rustbot release-assignment
+            mutation(
+                "DELETE",
+                "pulls/7/requested_reviewers",
+                serde_json::json!({"reviewers": ["triager"]})
+            ),
+            mutation(
+                "DELETE",
+                "issues/7/assignees",
+                serde_json::json!({"assignees": ["triager"]})
+            ),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn review_explain() {
+    let github = FakeGithub::start("review_explain");
+    let ctx = github.context();
+    github.deliver_webhooks(&ctx).await;
+
+    // The diff comes from the PR, since the issue of a comment has no base
+    // and head.
+    let comments = github.comments();
+    let [comment] = comments.as_slice() else {
+        panic!("expected one comment, got {comments:?}");
+    };
+    assert!(
+        comment.contains("`compiler/frob.rs` matches `/compiler`"),
+        "{comment}"
+    );
+    assert!(
+        comment.contains("Candidates: `compiler-reviewer`"),
+        "{comment}"
+    );
+    assert!(!comment.contains("not available"), "{comment}");
+}